@@ -1,66 +1,560 @@
-use std::fs::read_to_string;
-use itertools::Itertools;
+use std::collections::HashMap;
+use std::io::BufRead;
 
-fn read_lines_advent1(filename: &str) -> (Vec<i32>, Vec<i32>) {
-    let mut l: Vec<i32> = vec![];
-    let mut r: Vec<i32> = vec![];
+use crate::input::{self, InputError, Line, UNNAMED_INPUT};
+use crate::solution::{Answer, Solution};
 
-    read_to_string(filename) 
-        .unwrap()  // panic on possible file-reading errors
-        .lines()  // split the string into an iterator of string slices
-        .for_each(|s| {
-            let mut split = s.split("   ");
+/// Read `columns` whitespace-separated columns a line at a time from `reader`, returning one list per column.
+/// Blank lines are skipped.
+pub fn read_columns<R: BufRead>(mut reader: R, columns: usize) -> Result<Vec<Vec<i64>>, InputError> {
+    let mut lists: Vec<Vec<i64>> = vec![vec![]; columns];
+    let mut text = String::new();
+    let mut number = 0;
 
-            let ls = split.next().unwrap();
-            let rs = split.next().unwrap();
+    loop {
+        text.clear();
+        number += 1;
 
-            l.push(ls.parse().unwrap());
-            r.push(rs.parse().unwrap());
-        }) ;
+        let read = reader.read_line(&mut text)
+            .map_err(|source| InputError::Io { file: UNNAMED_INPUT.to_string(), source })?;
 
-    (l, r)
+        if read == 0 {
+            return Ok(lists);
+        }
+
+        let line = Line { number, text: text.trim_end_matches(['\n', '\r']) };
+        let mut fields = line.text.split_whitespace().peekable();
+
+        if fields.peek().is_none() {
+            continue;
+        }
+
+        for list in lists.iter_mut() {
+            let field = line.expect(&mut fields, &format!("{columns} values"))?;
+
+            list.push(line.parse(field)?);
+        }
+
+        if let Some(extra) = fields.next() {
+            return Err(line.error(extra, format!("expected only {columns} values")));
+        }
+    }
+}
+
+/// Read the two day 1 lists; see [`read_columns`].
+pub fn read_lists<R: BufRead>(reader: R) -> Result<(Vec<i64>, Vec<i64>), InputError> {
+    let mut lists = read_columns(reader, 2)?;
+    let r = lists.pop().unwrap_or_default();
+    let l = lists.pop().unwrap_or_default();
+
+    Ok((l, r))
+}
+
+/// How far apart two values are when pairing lists.
+pub trait Distance {
+    fn distance(&self, a: i64, b: i64) -> i64;
+}
+
+/// `|a - b|`, saturating at `i64::MAX` for values more than that far apart.
+pub struct Absolute;
+
+impl Distance for Absolute {
+    fn distance(&self, a: i64, b: i64) -> i64 {
+        i64::try_from(a.abs_diff(b)).unwrap_or(i64::MAX)
+    }
+}
+
+/// `(a - b)²`, saturating at `i64::MAX` for values more than about 3e9 apart.
+pub struct Squared;
+
+impl Distance for Squared {
+    fn distance(&self, a: i64, b: i64) -> i64 {
+        let difference = a.abs_diff(b);
+
+        i64::try_from(difference.saturating_mul(difference)).unwrap_or(i64::MAX)
+    }
+}
+
+impl<F: Fn(i64, i64) -> i64> Distance for F {
+    fn distance(&self, a: i64, b: i64) -> i64 {
+        self(a, b)
+    }
+}
+
+/// How to match the values of two lists against each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pairing {
+    /// Smallest with smallest, next with next, and so on; the puzzle's pairing.
+    SortedZip,
+    /// The pairing with the smallest total distance. Costs O(n³), so suits lists of a few hundred values.
+    NearestMatch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pair {
+    pub left: i64,
+    pub right: i64,
+    pub distance: i64,
+}
+
+/// Pair up `left` and `right`, leaving the extra values of the longer list unpaired. Nearest-match pairs come back in
+/// the order of `left`.
+pub fn pair_lists(left: &[i64], right: &[i64], pairing: Pairing, metric: &impl Distance) -> Vec<Pair> {
+    let pair = |l: i64, r: i64| Pair { left: l, right: r, distance: metric.distance(l, r) };
+
+    match pairing {
+        Pairing::SortedZip => {
+            let (mut left, mut right) = (left.to_vec(), right.to_vec());
+            left.sort_unstable();
+            right.sort_unstable();
+
+            left.into_iter().zip(right).map(|(l, r)| pair(l, r)).collect()
+        }
+        Pairing::NearestMatch if left.len() <= right.len() => {
+            let cost: Vec<Vec<i128>> = left.iter().map(|l| right.iter().map(|r| metric.distance(*l, *r).into()).collect()).collect();
+
+            assignment(&cost).into_iter().enumerate().map(|(i, j)| pair(left[i], right[j])).collect()
+        }
+        Pairing::NearestMatch => {
+            let cost: Vec<Vec<i128>> = right.iter().map(|r| left.iter().map(|l| metric.distance(*l, *r).into()).collect()).collect();
+
+            let mut matched: Vec<(usize, usize)> = assignment(&cost).into_iter().enumerate().map(|(j, i)| (i, j)).collect();
+            matched.sort_unstable();
+
+            matched.into_iter().map(|(i, j)| pair(left[i], right[j])).collect()
+        }
+    }
+}
+
+/// Widened to 128 bits, like [`ListTotals`], so summing many large distances cannot overflow.
+pub fn total_distance(pairs: &[Pair]) -> i128 {
+    pairs.iter().map(|p| i128::from(p.distance)).sum()
+}
+
+/// The Hungarian algorithm: for a cost matrix with no more rows than columns, the column given to each row so the
+/// total cost is smallest.
+fn assignment(cost: &[Vec<i128>]) -> Vec<usize> {
+    let rows = cost.len();
+    let columns = cost.first().map(|r| r.len()).unwrap_or(0);
+
+    // Potentials and matches are 1-based, with index 0 standing for "unassigned"
+    // Potentials are 128-bit so saturated 64-bit costs cannot overflow them
+    let mut u = vec![0i128; rows + 1];
+    let mut v = vec![0i128; columns + 1];
+    let mut row_for_column = vec![0usize; columns + 1];
+    let mut way = vec![0usize; columns + 1];
+
+    for row in 1..=rows {
+        row_for_column[0] = row;
+
+        let mut column = 0;
+        let mut min_slack = vec![i128::MAX; columns + 1];
+        let mut used = vec![false; columns + 1];
+
+        loop {
+            used[column] = true;
+
+            let current_row = row_for_column[column];
+            let mut delta = i128::MAX;
+            let mut next_column = 0;
+
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+
+                let slack = cost[current_row - 1][j - 1] - u[current_row] - v[j];
+
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = column;
+                }
+
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    next_column = j;
+                }
+            }
+
+            for j in 0..=columns {
+                if used[j] {
+                    u[row_for_column[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+
+            column = next_column;
+
+            if row_for_column[column] == 0 {
+                break;
+            }
+        }
+
+        while column != 0 {
+            let previous = way[column];
+            row_for_column[column] = row_for_column[previous];
+            column = previous;
+        }
+    }
+
+    let mut column_for_row = vec![0; rows];
+
+    for (column, row) in row_for_column.iter().enumerate().skip(1) {
+        if *row != 0 {
+            column_for_row[row - 1] = column - 1;
+        }
+    }
+
+    column_for_row
+}
+
+/// Both day 1 answers, worked out together.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ListTotals {
+    /// Sum of the distances between the lists' values paired up in sorted order.
+    pub distance: i128,
+    /// Sum of each left value times the number of times it appears in the right list.
+    pub similarity: i128,
+}
+
+/// Walk two sorted lists once, pairing values for the distance and counting matching runs for the similarity.
+///
+/// Totals are widened to 128 bits so no pair of `i64` lists can overflow them.
+pub fn list_totals(l: &[i64], r: &[i64]) -> ListTotals {
+    let distance = l.iter().zip(r).map(|(li, ri)| i128::from(li.abs_diff(*ri))).sum();
+
+    // Step through runs of equal values so repeated values are counted once per run, not once per copy
+    let run_length = |values: &[i64]| values.iter().take_while(|v| **v == values[0]).count();
+
+    let mut similarity = 0;
+    let (mut i, mut j) = (0, 0);
+
+    while i < l.len() {
+        let value = l[i];
+        let left_run = run_length(&l[i..]);
+
+        while j < r.len() && r[j] < value {
+            j += 1;
+        }
+
+        let right_run = if j < r.len() && r[j] == value { run_length(&r[j..]) } else { 0 };
+
+        similarity += i128::from(value) * (left_run * right_run) as i128;
+
+        i += left_run;
+        j += right_run;
+    }
+
+    ListTotals { distance, similarity }
+}
+
+/// How many times each value appears in a list.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frequencies {
+    counts: HashMap<i64, usize>,
+}
+
+impl Frequencies {
+    pub fn count(&self, value: i64) -> usize {
+        self.counts.get(&value).copied().unwrap_or(0)
+    }
+
+    /// Number of different values.
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Every value with its count, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (i64, usize)> + '_ {
+        self.counts.iter().map(|(value, count)| (*value, *count))
+    }
 }
 
-pub fn advent1a() {
-    let (mut l, mut r) = read_lines_advent1("advent1-input.txt");
+impl FromIterator<i64> for Frequencies {
+    fn from_iter<T: IntoIterator<Item = i64>>(values: T) -> Self {
+        let mut counts = HashMap::new();
+
+        for value in values {
+            *counts.entry(value).or_insert(0) += 1;
+        }
 
-    l.sort();
-    r.sort();
+        Frequencies { counts }
+    }
+}
+
+/// The puzzle's similarity score: each left value times the number of times it appears on the right. Swapping the
+/// lists gives the same score, since each value counts once per pair of copies.
+pub fn similarity(left: &Frequencies, right: &Frequencies) -> i128 {
+    left.iter().map(|(value, count)| i128::from(value) * (count * right.count(value)) as i128).sum()
+}
+
+/// The sum of the values the two lists have in common as multisets: each copy of a value matches at most one copy
+/// in the other list, so a value repeated many times in just one list counts only as often as the other has it.
+pub fn matched_similarity(left: &Frequencies, right: &Frequencies) -> i128 {
+    left.iter().map(|(value, count)| i128::from(value) * count.min(right.count(value)) as i128).sum()
+}
 
-    let diff : i32 = l.into_iter()
-        .zip(r.into_iter())
-        .map(|(li, ri)| {
-            (li - ri).abs()
+/// One value's share of the similarity score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contribution {
+    pub value: i64,
+    pub left_count: usize,
+    pub right_count: usize,
+    pub score: i128,
+}
+
+/// Every value found in both lists with what it adds to [`similarity`], largest score first.
+pub fn similarity_breakdown(left: &Frequencies, right: &Frequencies) -> Vec<Contribution> {
+    let mut contributions: Vec<Contribution> = left.iter()
+        .filter(|(value, _)| right.count(*value) > 0)
+        .map(|(value, left_count)| {
+            let right_count = right.count(value);
+
+            Contribution { value, left_count, right_count, score: i128::from(value) * (left_count * right_count) as i128 }
         })
-        .sum();
+        .collect();
+
+    contributions.sort_by(|a, b| b.score.cmp(&a.score).then(a.value.cmp(&b.value)));
+    contributions
+}
 
-    println!("{}", diff);
+pub struct Advent1 {
+    l: Vec<i64>,
+    r: Vec<i64>,
+}
+
+impl Advent1 {
+    pub fn from_lists(mut l: Vec<i64>, mut r: Vec<i64>) -> Advent1 {
+        l.sort_unstable();
+        r.sort_unstable();
+
+        Advent1 { l, r }
+    }
+
+    /// Pair the two lists with any pairing and metric; [`Advent1::part1`] is the sorted zip with [`Absolute`].
+    pub fn pairs(&self, pairing: Pairing, metric: &impl Distance) -> Vec<Pair> {
+        pair_lists(&self.l, &self.r, pairing, metric)
+    }
+
+    pub fn totals(&self) -> ListTotals {
+        list_totals(&self.l, &self.r)
+    }
+
+    pub fn left_frequencies(&self) -> Frequencies {
+        self.l.iter().copied().collect()
+    }
+
+    pub fn right_frequencies(&self) -> Frequencies {
+        self.r.iter().copied().collect()
+    }
+}
+
+impl Solution for Advent1 {
+    fn parse(input: &str) -> Result<Self, InputError> {
+        Self::from_reader(input.as_bytes())
+    }
+
+    fn from_reader<R: BufRead>(reader: R) -> Result<Self, InputError> {
+        let (l, r) = read_lists(reader)?;
+
+        Ok(Self::from_lists(l, r))
+    }
+
+    /// Streams the file rather than reading it into memory first.
+    fn from_file(filename: &str) -> Result<Self, InputError> {
+        Self::from_reader(input::open_input(filename)?).map_err(|e| e.in_file(filename))
+    }
+
+    fn part1(&self) -> Answer {
+        self.totals().distance.into()
+    }
+
+    fn part2(&self) -> Answer {
+        self.totals().similarity.into()
+    }
 }
 
 #[test]
-pub fn advent1b() {
-    let (mut l, mut r) = read_lines_advent1("advent1-input.txt");
+pub fn advent1a_run() {
+    assert_eq!(Answer::Signed(2344935), Advent1::from_file("inputs/advent1-input.txt").unwrap().part1());
+}
 
-    l.sort();
-    r.sort();
+#[test]
+pub fn advent1b_run() {
+    assert_eq!(Answer::Signed(27647262), Advent1::from_file("inputs/advent1-input.txt").unwrap().part2());
+}
 
-    let r_count = r.into_iter()
-                .map(|i| (i, i))
-                .into_group_map();
+#[test]
+fn any_whitespace_separates_values() {
+    let advent1 = Advent1::parse("3   4\n4\t3\n2 5  \r\n1 3\n\n3 9\n3 3\n\n").unwrap();
 
-    let similarity : i32 = l.into_iter()
-        .map(|li| {
-            let rv = r_count.get(&li);
+    assert_eq!(ListTotals { distance: 11, similarity: 31 }, advent1.totals());
+}
 
-            let mult = if let Some(rv) = rv {
-                rv.len() as i32
-            } else {
-                0
-            };
+#[test]
+fn malformed_lines() {
+    let error = |input: &str| Advent1::parse(input).err().unwrap().to_string();
 
-            li * mult
-        })
-        .sum();
+    assert_eq!("<input>:2:2: expected 2 values", error("1 2\n3\n"));
+    assert_eq!("<input>:1:5: expected only 2 values", error("1 2 3"));
+    assert_eq!("<input>:1:3: invalid number `x`", error("1 x"));
+}
+
+#[test]
+fn large_lists_match_naive_totals() {
+    use itertools::Itertools;
+
+    let rows = 200_000;
+    let value = |i: i64| (i * 7_919) % 100_003 - 50_000;
+    let input: String = (0..rows).map(|i| format!("{}\t{}\n", value(i), value(i * 3 + 1))).collect();
+
+    let advent1 = Advent1::parse(&input).unwrap();
+
+    let counts = advent1.r.iter().counts();
+    let naive_similarity: i128 = advent1.l.iter().map(|li| i128::from(*li) * *counts.get(li).unwrap_or(&0) as i128).sum();
+
+    assert_eq!(rows as usize, advent1.l.len());
+    assert_eq!(naive_similarity, advent1.totals().similarity);
+}
+
+#[test]
+fn extreme_values_do_not_overflow() {
+    let advent1 = Advent1::parse("9223372036854775807 -1\n9223372036854775807 9223372036854775807\n").unwrap();
+
+    assert_eq!(Answer::Wide(i64::MAX as i128 + 1), advent1.part1());
+    assert_eq!(Answer::Wide(2 * i64::MAX as i128), advent1.part2());
+
+    let totals = list_totals(&[i64::MAX; 3], &[i64::MAX; 2]);
+    assert_eq!(6 * i64::MAX as i128, totals.similarity);
+}
+
+#[test]
+fn repeated_values_count_every_pair() {
+    let totals = list_totals(&[2, 2, 2, 5], &[1, 2, 2, 5]);
+
+    assert_eq!(ListTotals { distance: 1, similarity: 17 }, totals);
+}
+
+#[test]
+fn frequencies_count_values() {
+    let frequencies: Frequencies = [3, 4, 3, 3, -1].into_iter().collect();
+
+    assert_eq!(3, frequencies.count(3));
+    assert_eq!(1, frequencies.count(-1));
+    assert_eq!(0, frequencies.count(5));
+    assert_eq!(3, frequencies.distinct());
+}
+
+#[test]
+fn similarity_variants_and_breakdown() {
+    let advent1 = Advent1::parse("3   4\n4   3\n2   5\n1   3\n3   9\n3   3").unwrap();
+    let (left, right) = (advent1.left_frequencies(), advent1.right_frequencies());
+
+    assert_eq!(31, similarity(&left, &right));
+    assert_eq!(31, similarity(&right, &left));
+    assert_eq!(13, matched_similarity(&left, &right));
+    assert_eq!(13, matched_similarity(&right, &left));
+
+    assert_eq!(
+        vec![
+            Contribution { value: 3, left_count: 3, right_count: 3, score: 27 },
+            Contribution { value: 4, left_count: 1, right_count: 1, score: 4 },
+        ],
+        similarity_breakdown(&left, &right)
+    );
+}
 
-    println!("{}", similarity);
-}
\ No newline at end of file
+#[test]
+fn hash_counts_match_merged_totals() {
+    let advent1 = Advent1::from_file("inputs/advent1-input.txt").unwrap();
+    let (left, right) = (advent1.left_frequencies(), advent1.right_frequencies());
+
+    assert_eq!(advent1.totals().similarity, similarity(&left, &right));
+    assert_eq!(advent1.totals().similarity, similarity_breakdown(&left, &right).iter().map(|c| c.score).sum());
+}
+
+#[test]
+fn read_any_number_of_columns() {
+    let columns = read_columns("1 2 3\n4\t5 6\n\n7 8 9\n".as_bytes(), 3).unwrap();
+
+    assert_eq!(vec![vec![1, 4, 7], vec![2, 5, 8], vec![3, 6, 9]], columns);
+
+    let err = read_columns("1 2 3\n4 5\n".as_bytes(), 3).err().unwrap();
+    assert_eq!("<input>:2:4: expected 3 values", err.to_string());
+}
+
+#[test]
+fn distance_metrics() {
+    let offset_by_ten = |a: i64, b: i64| (a - b - 10).abs();
+
+    assert_eq!(3, Absolute.distance(1, 4));
+    assert_eq!(9, Squared.distance(1, 4));
+    assert_eq!(13, offset_by_ten.distance(1, 4));
+}
+
+#[test]
+fn distances_saturate_instead_of_overflowing() {
+    assert_eq!(i64::MAX, Absolute.distance(i64::MIN, i64::MAX));
+    assert_eq!(i64::MAX, Absolute.distance(i64::MAX, -1));
+    assert_eq!(i64::MAX - 1, Absolute.distance(i64::MAX, 1));
+    assert_eq!(9_000_000_000_000_000_000, Squared.distance(0, 3_000_000_000));
+    assert_eq!(i64::MAX, Squared.distance(0, 4_000_000_000));
+
+    let pairs = pair_lists(&[i64::MIN, i64::MAX], &[i64::MAX, i64::MIN], Pairing::NearestMatch, &Squared);
+
+    assert_eq!(0, total_distance(&pairs));
+
+    let pairs = pair_lists(&[i64::MIN, i64::MIN], &[i64::MAX, i64::MAX], Pairing::SortedZip, &Absolute);
+
+    assert_eq!(2 * i64::MAX as i128, total_distance(&pairs));
+}
+
+#[test]
+fn sorted_zip_pairing_matches_part1() {
+    let advent1 = Advent1::from_file("inputs/advent1-input.txt").unwrap();
+
+    assert_eq!(advent1.totals().distance, total_distance(&advent1.pairs(Pairing::SortedZip, &Absolute)));
+}
+
+#[test]
+fn nearest_match_minimises_custom_metric() {
+    let mismatch = |a: i64, b: i64| i64::from(a != b);
+
+    let left = [1, 2, 3];
+    let right = [4, 3, 2];
+
+    assert_eq!(3, total_distance(&pair_lists(&left, &right, Pairing::SortedZip, &mismatch)));
+
+    let nearest = pair_lists(&left, &right, Pairing::NearestMatch, &mismatch);
+
+    assert_eq!(1, total_distance(&nearest));
+    assert!(nearest.contains(&Pair { left: 2, right: 2, distance: 0 }));
+    assert!(nearest.contains(&Pair { left: 3, right: 3, distance: 0 }));
+}
+
+#[test]
+fn nearest_match_with_uneven_lists() {
+    let pairs = pair_lists(&[10, 20, 30], &[29, 11], Pairing::NearestMatch, &Squared);
+
+    assert_eq!(vec![Pair { left: 10, right: 11, distance: 1 }, Pair { left: 30, right: 29, distance: 1 }], pairs);
+
+    let pairs = pair_lists(&[5], &[0, 4, 9], Pairing::NearestMatch, &Absolute);
+
+    assert_eq!(vec![Pair { left: 5, right: 4, distance: 1 }], pairs);
+}
+
+#[test]
+fn sorted_zip_is_optimal_for_convex_metrics() {
+    let left = [7, -3, 12, 0, 5, 5, 40];
+    let right = [1, 9, -8, 6, 30, 2, 11];
+
+    let sorted = pair_lists(&left, &right, Pairing::SortedZip, &Absolute);
+    let nearest = pair_lists(&left, &right, Pairing::NearestMatch, &Absolute);
+    assert_eq!(total_distance(&sorted), total_distance(&nearest));
+
+    let sorted = pair_lists(&left, &right, Pairing::SortedZip, &Squared);
+    let nearest = pair_lists(&left, &right, Pairing::NearestMatch, &Squared);
+    assert_eq!(total_distance(&sorted), total_distance(&nearest));
+}
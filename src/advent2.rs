@@ -1,28 +1,30 @@
-use std::{fs::read_to_string, io::Error};
 use itertools::Itertools;
+use log::debug;
 
+use crate::input::{self, InputError};
+use crate::solution::{Answer, Solution};
+
+/// Which of the safety checks a report failed.
 #[derive(Debug)]
-struct ErrInfo {
-    all_increasing: bool,
-    all_decreasing: bool,
-    all_diffs_safe: bool,
+pub struct ErrInfo {
+    pub all_increasing: bool,
+    pub all_decreasing: bool,
+    pub all_diffs_safe: bool,
 }
 
-fn read_lines(filename: &str) -> Vec<Vec<i32>> {
-    read_to_string(filename) 
-        .unwrap()  // panic on possible file-reading errors
-        .lines()  // split the string into an iterator of string slices
-        .map(|s| {
-            let split = s.split(" ");
+pub fn read_lines(contents: &str) -> Result<Vec<Vec<i32>>, InputError> {
+    input::lines(contents)
+        .map(|line| {
+            let split = line.text.split(" ");
 
-            split.map(|s| s.parse::<i32>().unwrap())
+            split.map(|s| line.parse::<i32>(s))
                 .collect()
         })
         .collect()
 }
 
-fn safe_levels(levels: &Vec<i32>) -> Result<(), ErrInfo> {
-    let deltas : Vec<i32> = levels.windows(2).map(|w| (w[0] - w[1]) )
+pub fn safe_levels(levels: &[i32]) -> Result<(), ErrInfo> {
+    let deltas : Vec<i32> = levels.windows(2).map(|w| w[0] - w[1])
         .collect();
 
     let iter = deltas.into_iter();
@@ -34,48 +36,63 @@ fn safe_levels(levels: &Vec<i32>) -> Result<(), ErrInfo> {
     if (all_increasing || all_decreasing) && all_diffs_safe {
         Ok(())
     } else {
-        Result::Err(ErrInfo { all_increasing: all_increasing, all_decreasing: all_decreasing, all_diffs_safe: all_diffs_safe })
+        Result::Err(ErrInfo { all_increasing, all_decreasing, all_diffs_safe })
     }
 }
 
-fn safe_levels_problem_dampner(levels: &Vec<i32>) -> Result<(), ErrInfo> {
+/// Like `safe_levels`, but a report is also safe if removing any single level makes it safe.
+pub fn safe_levels_problem_dampner(levels: &[i32]) -> Result<(), ErrInfo> {
     let res = safe_levels(levels);
 
     if res.is_err() {
         for idx in 0..levels.len() {
-            let mut mod_levels = levels.clone();
+            let mut mod_levels = levels.to_vec();
             mod_levels.remove(idx);
 
             if safe_levels(&mod_levels).is_ok() {
-                println!("Levels saved by removing {idx}: {}", levels.into_iter().join(":"));
+                debug!("Levels saved by removing {idx}: {}", levels.iter().join(":"));
                 return Ok(())
             }
         }
 
-        println!("Levels could not be saved: {:?} - {}", res, levels.into_iter().join(":"));
+        debug!("Levels could not be saved: {:?} - {}", res, levels.iter().join(":"));
     }
 
-    return res
+    res
 }
 
-pub fn advent2a() {
-    let input = read_lines("advent2a-input.txt");
+pub struct Advent2 {
+    reports: Vec<Vec<i32>>,
+}
 
-    let safe_levels = input.into_iter()
-                        .map(|x| safe_levels(&x))
-                        .filter(|x| x.is_ok())
-                        .count();
+impl Solution for Advent2 {
+    fn parse(input: &str) -> Result<Self, InputError> {
+        Ok(Self { reports: read_lines(input)? })
+    }
 
-    println!("Safe levels: {}", safe_levels);
-}
+    fn part1(&self) -> Answer {
+        self.reports.iter()
+            .map(|x| safe_levels(x))
+            .filter(|x| x.is_ok())
+            .count()
+            .into()
+    }
 
-pub fn advent2b() {
-    let input = read_lines("advent2a-input.txt");
+    fn part2(&self) -> Answer {
+        self.reports.iter()
+            .map(|x| safe_levels_problem_dampner(x))
+            .filter(|x| x.is_ok())
+            .count()
+            .into()
+    }
+}
 
-    let safe_levels = input.into_iter()
-                        .map(|x| safe_levels_problem_dampner(&x))
-                        .filter(|x| x.is_ok())
-                        .count();
+#[test]
+pub fn advent2a_test() {
+    assert_eq!(Answer::Unsigned(2), Advent2::from_file("inputs/advent2a-test.txt").unwrap().part1());
+}
 
-    println!("Safe levels: {}", safe_levels);
-}
\ No newline at end of file
+#[test]
+pub fn advent2b_test() {
+    assert_eq!(Answer::Unsigned(4), Advent2::from_file("inputs/advent2a-test.txt").unwrap().part2());
+}
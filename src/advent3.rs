@@ -1,63 +1,104 @@
-use std::{fs::read_to_string, io::Error, ops::IndexMut, slice::RChunks};
-use itertools::Itertools;
 use regex::Regex;
 
-#[derive(Debug)]
-struct ErrInfo {
-    all_increasing: bool,
-    all_decreasing: bool,
-    all_diffs_safe: bool,
-}
+use crate::input::{self, InputError};
+use crate::solution::{Answer, Solution};
 
-fn read_lines(filename: &str) -> String {
-    read_to_string(filename) 
-        .unwrap()  // panic on possible file-reading errors
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Instruction {
+    Mul(i32, i32),
+    Do,
+    Dont,
 }
 
-pub fn advent3a() {
-    let data = read_lines("advent3a-input.txt");
+pub fn read_instructions(data: &str) -> Result<Vec<Instruction>, InputError> {
+    let pattern = Regex::new(r#"((?P<mul>mul)\((\d+),(\d+)\)|(?P<enable>do|don't)\(\))"#).unwrap();
 
-    let pattern = Regex::new(r#"mul\((\d+),(\d+)\)"#).unwrap();
+    let parse_operand = |m: regex::Match| {
+        m.as_str().parse::<i32>().map_err(|_| {
+            let (line, column) = input::position_of(data, m.start());
 
-    let captures = pattern.captures_iter(data.as_str()).map(|c| c.extract());
+            InputError::Parse {
+                file: input::UNNAMED_INPUT.to_string(),
+                line,
+                column,
+                message: format!("invalid number `{}`", m.as_str()),
+            }
+        })
+    };
 
-    let result : i32 = captures.map( |(_, [lhs, rhs])| lhs.parse::<i32>().unwrap() * rhs.parse::<i32>().unwrap())
-        .sum();
-    
-    println!("{}", result);
+    pattern.captures_iter(data)
+        .map(|c| {
+            if let Some(enable_instr) = c.name("enable") {
+                match enable_instr.as_str() {
+                    "do" => Ok(Instruction::Do),
+                    _ => Ok(Instruction::Dont),
+                }
+            } else {
+                let lhs = parse_operand(c.get(3).unwrap())?;
+                let rhs = parse_operand(c.get(4).unwrap())?;
+
+                Ok(Instruction::Mul(lhs, rhs))
+            }
+        })
+        .collect()
 }
 
-pub fn advent3b() {
-    let data = read_lines("advent3a-input.txt");
+pub struct Advent3 {
+    instructions: Vec<Instruction>,
+}
 
-    let pattern = Regex::new(r#"((?P<mul>mul)\((\d+),(\d+)\)|(?P<enable>do|don't)\(\))"#).unwrap();
+impl Solution for Advent3 {
+    fn parse(input: &str) -> Result<Self, InputError> {
+        Ok(Self { instructions: read_instructions(input)? })
+    }
+
+    fn part1(&self) -> Answer {
+        advent3a(&self.instructions).into()
+    }
+
+    fn part2(&self) -> Answer {
+        advent3b(&self.instructions).into()
+    }
+}
 
-    let captures = pattern.captures_iter(data.as_str());
+pub fn advent3a(instructions: &[Instruction]) -> i32 {
+    instructions.iter()
+        .map(|instruction| match instruction {
+            Instruction::Mul(lhs, rhs) => lhs * rhs,
+            _ => 0,
+        })
+        .sum()
+}
 
+pub fn advent3b(instructions: &[Instruction]) -> i32 {
     let mut result : i32 = 0;
     let mut enabled: bool = true;
 
-    captures.for_each( |c| {
-        if let Some(enable_instr) = c.name("enable") {
-            match enable_instr.as_str() {
-                "do" => {
-                    enabled = true;
-                },
-                "don't" => {
-                    enabled = false ;
-                },
-                _ => panic!("Unknown enable instruction")
+    instructions.iter().for_each( |instruction| {
+        match instruction {
+            Instruction::Do => {
+                enabled = true;
+            },
+            Instruction::Dont => {
+                enabled = false ;
+            },
+            Instruction::Mul(lhs, rhs) => {
+                if enabled {
+                    result += lhs * rhs;
+                }
             }
-        } else {
-            if enabled {
-                let lhs = c.get(3).unwrap().as_str().parse::<i32>().unwrap();
-                let rhs = c.get(4).unwrap().as_str().parse::<i32>().unwrap();
-
-                result += lhs * rhs;
-            }
-            
         }
     });
 
-    println!("{}", result);
-}
\ No newline at end of file
+    result
+}
+
+#[test]
+pub fn advent3a_test() {
+    assert_eq!(Answer::Signed(161), Advent3::from_file("inputs/advent3a-test.txt").unwrap().part1());
+}
+
+#[test]
+pub fn advent3b_test() {
+    assert_eq!(Answer::Signed(48), Advent3::from_file("inputs/advent3a-test.txt").unwrap().part2());
+}
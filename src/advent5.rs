@@ -1,72 +1,264 @@
-use itertools::{iproduct, Itertools};
-use regex::Regex;
-use std::{
-    collections::HashMap, fs::read_to_string, io::Error, ops::{Index, IndexMut}, slice::RChunks
-};
-
-fn read_order_lines(filename: &str) -> Vec<(usize, usize)> {
-    read_to_string(filename)
-        .unwrap() // panic on possible file-reading errors
-        .lines() // split the string into an iterator of string slices
-        .map(|s| {
-            let mut parts = s.split("|");
-            (parts.next().unwrap().parse::<usize>().unwrap(), parts.next().unwrap().parse::<usize>().unwrap())
+pub mod graph;
+
+use itertools::Itertools;
+use log::{debug, trace, warn};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+
+use crate::input::{self, InputError, Line};
+use crate::solution::{Answer, Solution};
+
+pub fn read_order_lines<'a>(lines: impl Iterator<Item = Line<'a>>) -> Result<Vec<(usize, usize)>, InputError> {
+    lines
+        .map(|line| {
+            let mut parts = line.text.split("|");
+
+            let before = line.expect(&mut parts, "`before|after` page pair")?;
+            let after = line.expect(&mut parts, "`|` followed by a page number")?;
+
+            Ok((line.parse::<usize>(before)?, line.parse::<usize>(after)?))
         })
         .collect()
 }
 
-fn read_update_lines(filename: &str) -> Vec<Vec<usize>> {
-    read_to_string(filename)
-        .unwrap() // panic on possible file-reading errors
-        .lines() // split the string into an iterator of string slices
-        .map(|s| {
-            let mut parts = s.split(",");
+pub fn read_update_lines<'a>(lines: impl Iterator<Item = Line<'a>>) -> Result<Vec<Vec<usize>>, InputError> {
+    lines
+        .map(|line| {
+            let parts = line.text.split(",");
 
-            parts.map(|v| v.parse::<usize>().unwrap())
+            parts.map(|v| line.parse::<usize>(v))
                 .collect()
         })
         .collect()
 }
 
+/// A rule that clashes with the rules already in a [`PageOrdering`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuleConflict {
+    Duplicate { before: usize, after: usize },
+    /// `page|page`, which no update containing the page could satisfy.
+    SelfRule { page: usize },
+    /// `before|after` was added when `after|before` was already a rule. Both are kept, as a two-page cycle.
+    Contradiction { before: usize, after: usize },
+}
+
+impl Display for RuleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleConflict::Duplicate { before, after } => write!(f, "rule {before}|{after} is listed more than once"),
+            RuleConflict::SelfRule { page } => write!(f, "rule {page}|{page} puts a page before itself"),
+            RuleConflict::Contradiction { before, after } => {
+                write!(f, "rule {before}|{after} contradicts the rule {after}|{before}")
+            }
+        }
+    }
+}
+
+impl Error for RuleConflict {}
+
+/// The page ordering rules, indexed for constant-time precedence checks.
+#[derive(Clone, Debug, Default)]
+pub struct PageOrdering {
+    rules: HashSet<(usize, usize)>,
+    successors: HashMap<usize, Vec<usize>>,
+}
+
+impl PageOrdering {
+    pub fn new() -> PageOrdering {
+        PageOrdering::default()
+    }
+
+    /// Build from `before|after` pairs, skipping duplicates and keeping contradictions but rejecting self-rules.
+    pub fn from_rules(rules: impl IntoIterator<Item = (usize, usize)>) -> Result<PageOrdering, RuleConflict> {
+        let mut ordering = PageOrdering::new();
+
+        for (before, after) in rules {
+            match ordering.insert(before, after) {
+                Ok(()) | Err(RuleConflict::Duplicate { .. } | RuleConflict::Contradiction { .. }) => {}
+                Err(conflict) => return Err(conflict),
+            }
+        }
+
+        Ok(ordering)
+    }
+
+    /// Add the rule `before|after`. Duplicates and self-rules are rejected and leave the rules unchanged; a
+    /// contradiction is added anyway, so the cycle it forms can be reported, but still returned as an error.
+    pub fn insert(&mut self, before: usize, after: usize) -> Result<(), RuleConflict> {
+        if self.must_precede(before, after) {
+            return Err(RuleConflict::Duplicate { before, after });
+        }
+
+        if before == after {
+            return Err(RuleConflict::SelfRule { page: before });
+        }
+
+        self.add(before, after);
+
+        if self.must_precede(after, before) {
+            return Err(RuleConflict::Contradiction { before, after });
+        }
+
+        Ok(())
+    }
+
+    fn add(&mut self, before: usize, after: usize) {
+        self.rules.insert((before, after));
+        self.successors.entry(before).or_default().push(after);
+    }
+
+    pub fn must_precede(&self, before: usize, after: usize) -> bool {
+        self.rules.contains(&(before, after))
+    }
 
+    /// Pages that must come after `page`.
+    pub fn successors(&self, page: usize) -> &[usize] {
+        self.successors.get(&page).map(Vec::as_slice).unwrap_or_default()
+    }
 
-fn update_is_in_order(update: &Vec<usize>, rules:&HashMap<usize, Vec<usize>>) -> bool {
-    for pivot in 1..update.len() {
-        let current = update[ pivot ];
-        let prior = &update[0..pivot];
+    /// Every rule as a `(before, after)` pair, sorted.
+    pub fn rules(&self) -> Vec<(usize, usize)> {
+        self.rules.iter().copied().sorted().collect()
+    }
 
-        let rules_for_current = rules.get(&current);
-        
-        if let Some(rules_for_current) = rules_for_current {
-            for rule in rules_for_current {
-                if prior.contains(rule) {
-                    println!("Update failed due to {rule} preceeding {current} in position {pivot}");
-                    return false
-                }
+    /// Every page mentioned by a rule.
+    pub fn pages(&self) -> BTreeSet<usize> {
+        self.rules.iter().flat_map(|(before, after)| [*before, *after]).collect()
+    }
+
+    /// Only the rules between `pages`, such as the pages of one update.
+    pub fn restricted_to(&self, pages: &[usize]) -> PageOrdering {
+        let pages: HashSet<usize> = pages.iter().copied().collect();
+        let mut restricted = PageOrdering::new();
+
+        for (before, after) in self.rules() {
+            if pages.contains(&before) && pages.contains(&after) {
+                restricted.add(before, after);
             }
         }
+
+        restricted
     }
 
-    return true;
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Comparator for sorting `update` with `sort_by`, ranking each page by where [`fix_broken_rules`] puts it.
+    ///
+    /// The rank makes it a total order even where the rules leave pages unordered; pages not in `update` sort last.
+    /// Building it costs a full topological sort of `update`, so make one per update rather than per comparison.
+    pub fn comparator(&self, update: &[usize]) -> Result<impl Fn(&usize, &usize) -> Ordering, RuleCycle> {
+        let rank: HashMap<usize, usize> = fix_broken_rules(update, self)?.into_iter()
+            .enumerate()
+            .map(|(rank, page)| (page, rank))
+            .collect();
+
+        Ok(move |a: &usize, b: &usize| {
+            let rank_of = |page| rank.get(page).copied().unwrap_or(usize::MAX);
+
+            rank_of(a).cmp(&rank_of(b))
+        })
+    }
 }
 
-fn load_data_for_token(token: &str) -> (Vec<(usize, usize)>, Vec<Vec<usize>>) {
-    let order_items = read_order_lines(format!("advent5a-{}-order.txt", token).as_str());
+/// A rule `before|after` broken by an update, with the (0-based) positions of both pages in it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    pub before: usize,
+    pub after: usize,
+    pub before_position: usize,
+    pub after_position: usize,
+}
 
-    let updates = read_update_lines(format!("advent5a-{}-update.txt", token).as_str());
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "rule {}|{} broken: {} at position {} comes after {} at position {}",
+            self.before, self.after, self.before, self.before_position + 1, self.after, self.after_position + 1
+        )
+    }
+}
 
-    (order_items, updates)
+/// Every rule the update breaks, ordered by the position of the page that should have come first and then of the
+/// page that should have come after it.
+pub fn violations<'a>(update: &'a [usize], rules: &'a PageOrdering) -> impl Iterator<Item = Violation> + 'a {
+    update.iter().enumerate().flat_map(move |(before_position, before)| {
+        update[..before_position].iter().enumerate()
+            .filter(move |(_, after)| rules.must_precede(*before, **after))
+            .map(move |(after_position, after)| Violation { before: *before, after: *after, before_position, after_position })
+    })
+}
 
+pub fn rule_violations(update: &[usize], rules: &PageOrdering) -> Vec<Violation> {
+    violations(update, rules).collect()
 }
 
-fn updates_in_order(token: &str) -> Vec<usize> {
-    let order_items = read_order_lines(format!("advent5a-{}-order.txt", token).as_str());
+/// One update's audit: every rule it breaks and, if its rules form a cycle, why it cannot be reordered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateAudit<'a> {
+    pub update: &'a [usize],
+    pub violations: Vec<Violation>,
+    pub cycle: Option<RuleCycle>,
+}
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+/// The audit of every update, printed one update at a time followed by a summary.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditReport<'a> {
+    pub updates: Vec<UpdateAudit<'a>>,
+}
 
+impl Display for AuditReport<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, audit) in self.updates.iter().enumerate() {
+            let pages = audit.update.iter().join(",");
 
-    let updates = read_update_lines(format!("advent5a-{}-update.txt", token).as_str());
+            if audit.violations.is_empty() {
+                writeln!(f, "update {}: {pages}: in order", idx + 1)?;
+            } else {
+                writeln!(f, "update {}: {pages}: {} rule(s) broken", idx + 1, audit.violations.len())?;
+            }
+
+            for violation in &audit.violations {
+                writeln!(f, "  {violation}")?;
+            }
+
+            if let Some(cycle) = &audit.cycle {
+                writeln!(f, "  cannot be reordered: {cycle}")?;
+            }
+        }
+
+        let broken = self.updates.iter().filter(|audit| !audit.violations.is_empty()).count();
+
+        writeln!(f, "{} of {} updates out of order", broken, self.updates.len())
+    }
+}
+
+pub fn update_is_in_order(update: &[usize], rules: &PageOrdering) -> bool {
+    match violations(update, rules).next() {
+        Some(violation) => {
+            debug!("Update {update:?} out of order: {violation}");
+            false
+        }
+        None => true,
+    }
+}
+
+#[cfg(test)]
+fn load_data_for_token(token: &str) -> Advent5 {
+    Advent5::from_file(&format!("inputs/advent5a-{token}.txt")).unwrap()
+}
+
+#[cfg(test)]
+fn updates_in_order(token: &str) -> Vec<usize> {
+    let Advent5 { order_rules, updates } = load_data_for_token(token);
 
     updates.iter().enumerate()
         .filter(|(_idx, update)| update_is_in_order(update, &order_rules))
@@ -74,68 +266,240 @@ fn updates_in_order(token: &str) -> Vec<usize> {
         .collect()
 }
 
+pub fn middle_page_sum(updates: &[Vec<usize>]) -> u32 {
+    updates.iter()
+        .map(|v| v[v.len() / 2] as u32)
+        .sum()
+}
+
+pub struct Advent5 {
+    order_rules: PageOrdering,
+    updates: Vec<Vec<usize>>,
+}
+
+/// Read the rules section, naming it in any error.
+fn parse_rules<'a>(lines: impl Iterator<Item = Line<'a>>) -> Result<PageOrdering, InputError> {
+    let lines: Vec<Line> = lines.collect();
+    let pairs = read_order_lines(lines.iter().copied()).map_err(|e| e.in_section("rules"))?;
+
+    let mut rules = PageOrdering::new();
+
+    for (line, (before, after)) in lines.iter().zip(pairs) {
+        match rules.insert(before, after) {
+            Ok(()) => {}
+            Err(conflict @ (RuleConflict::Duplicate { .. } | RuleConflict::Contradiction { .. })) => {
+                warn!("line {}: {conflict}", line.number)
+            }
+            Err(conflict) => return Err(line.error_at(1, conflict.to_string()).in_section("rules")),
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Read the updates section, skipping blank lines but rejecting a section with no updates at all.
+///
+/// Updates whose rules form a cycle are kept: part 1 and the diagnostics still apply to them, and
+/// [`fix_broken_rules`] reports the cycle if one needs reordering.
+fn parse_updates<'a>(lines: impl Iterator<Item = Line<'a>>) -> Result<Vec<Vec<usize>>, InputError> {
+    let lines: Vec<Line> = lines.filter(|line| !line.text.trim().is_empty()).collect();
+
+    if lines.is_empty() {
+        return Err(InputError::invalid("no updates; they follow the rules after a blank line").in_section("updates"));
+    }
+
+    if let Some(line) = lines.iter().find(|line| line.text.contains('|')) {
+        return Err(line.error_at(1, "ordering rule after the blank line that ends the rules").in_section("updates"));
+    }
+
+    read_update_lines(lines.iter().copied()).map_err(|e| e.in_section("updates"))
+}
+
+impl Advent5 {
+    /// Load the older two-file layout, with the rules and the updates in files of their own.
+    pub fn from_files(rules_file: &str, updates_file: &str) -> Result<Advent5, InputError> {
+        let rules = input::read_input(rules_file)?;
+        let updates = input::read_input(updates_file)?;
+
+        let order_rules = parse_rules(input::lines(&rules).filter(|line| !line.text.trim().is_empty()))
+            .map_err(|e| e.in_file(rules_file))?;
+        let updates = parse_updates(input::lines(&updates)).map_err(|e| e.in_file(updates_file))?;
+
+        Ok(Advent5 { order_rules, updates })
+    }
+
+    pub fn rules(&self) -> &PageOrdering {
+        &self.order_rules
+    }
+
+    pub fn updates(&self) -> &[Vec<usize>] {
+        &self.updates
+    }
+
+    /// Each update with every rule it breaks, and the cycle that stops it being reordered if there is one.
+    pub fn audit(&self) -> AuditReport<'_> {
+        let updates = self.updates.iter()
+            .map(|update| UpdateAudit {
+                update,
+                violations: rule_violations(update, &self.order_rules),
+                cycle: fix_broken_rules(update, &self.order_rules).err(),
+            })
+            .collect();
+
+        AuditReport { updates }
+    }
+}
+
+impl Solution for Advent5 {
+    /// The ordering rules come first, followed by a blank line and then the updates.
+    fn parse(input: &str) -> Result<Self, InputError> {
+        let mut lines = input::lines(input);
+
+        let rules = parse_rules(lines.by_ref().take_while(|line| !line.text.trim().is_empty()))?;
+        let updates = parse_updates(lines)?;
+
+        Ok(Self { order_rules: rules, updates })
+    }
+
+    fn part1(&self) -> Answer {
+        let passing_updates: Vec<Vec<usize>> = self.updates.iter()
+                .filter(|update| update_is_in_order(update, &self.order_rules))
+                .cloned()
+                .collect();
+
+        middle_page_sum(&passing_updates).into()
+    }
+
+    /// Updates that cannot be reordered because their rules form a cycle are left out, with a warning.
+    fn part2(&self) -> Answer {
+        let fixed_updates: Vec<Vec<usize>> = self.updates.iter().enumerate()
+                .filter(|(_, update)| !update_is_in_order(update, &self.order_rules))
+                .filter_map(|(idx, update)| match fix_broken_rules(update, &self.order_rules) {
+                    Ok(fixed) => Some(fixed),
+                    Err(cycle) => {
+                        warn!("update {} cannot be reordered and is left out: {cycle}", idx + 1);
+                        None
+                    }
+                })
+                .collect();
+
+        middle_page_sum(&fixed_updates).into()
+    }
+}
+
 #[test]
 fn find_correct_rule_sum() {
-    let (order_items, updates) = load_data_for_token("input");
+    assert_eq!(Answer::Unsigned(143), load_data_for_token("test").part1());
+}
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+#[test]
+fn find_correct_rule_sum_input() {
+    assert_eq!(Answer::Unsigned(4959), load_data_for_token("input").part1());
+}
 
-    let passing_rules: Vec<usize> = updates.iter().enumerate()
-            .filter(|(_idx, update)| update_is_in_order(update, &order_rules))
-            .map(|(idx, _update)| idx)
-            .collect();
+/// The rules that apply to one update contradict each other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleCycle {
+    /// Each page must come before the next, and the last before the first.
+    pub pages: Vec<usize>,
+}
 
-    println!("Passing rules: {}", passing_rules.len());
+impl Display for RuleCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ordering rules form a cycle: {}", self.pages.iter().chain(self.pages.first()).join(" -> "))
+    }
+}
+
+impl Error for RuleCycle {}
+
+/// Reorder an update so no rule is violated, by topologically sorting the rules between its pages.
+///
+/// Pages are taken in their original order, and a page that must come before one being placed is pulled forward to
+/// just ahead of it. That keeps the original order as far as the rules allow, though a page pulled forward can still
+/// overtake pages the rules leave free.
+pub fn fix_broken_rules(update: &[usize], rules: &PageOrdering) -> Result<Vec<usize>, RuleCycle> {
+    let index_of: HashMap<usize, usize> = update.iter().enumerate().map(|(idx, page)| (*page, idx)).collect();
+
+    // Rule edges between the update's pages, as indexes into the update, from each page back to those before it
+    let mut predecessors: Vec<Vec<usize>> = vec![vec![]; update.len()];
 
-    dbg!(&passing_rules);
+    for (idx, page) in update.iter().enumerate() {
+        for after in rules.successors(*page) {
+            if let Some(next) = index_of.get(after) {
+                predecessors[*next].push(idx);
+            }
+        }
+    }
 
-    let middle_page_sum : u32 = passing_rules.into_iter()
-        .map(|idx| &updates[idx])
-        .map(|v| v.get( (v.len() / 2) ).unwrap() )
-        .map(|v| *v as u32 )
-        .sum();
+    predecessors.iter_mut().for_each(|before| before.sort_unstable());
 
-    assert_eq!(143, middle_page_sum);
+    let mut sort = StableSort { update, predecessors, placed: vec![false; update.len()], path: vec![], order: vec![] };
+
+    for idx in 0..update.len() {
+        sort.place(idx)?;
+    }
+
+    Ok(sort.order)
 }
 
-fn fix_broken_rules(input_update: &Vec<usize>, rules:&HashMap<usize, Vec<usize>>) -> Vec<usize> {
-    let mut update = input_update.clone();
+/// Depth-first state for [`fix_broken_rules`].
+struct StableSort<'a> {
+    update: &'a [usize],
+    predecessors: Vec<Vec<usize>>,
+    placed: Vec<bool>,
+    /// Pages being placed, each waiting on the one after it.
+    path: Vec<usize>,
+    order: Vec<usize>,
+}
 
-    for pivot in 1..update.len() {
-        let current = update[ pivot ];
-        let prior = &update[0..pivot];
+impl StableSort<'_> {
+    fn place(&mut self, idx: usize) -> Result<(), RuleCycle> {
+        if self.placed[idx] {
+            return Ok(());
+        }
 
-        let rules_for_current = rules.get(&current);
-        
-        if let Some(rules_for_current) = rules_for_current {
-            for rule in rules_for_current {
-                let violator = prior.into_iter().enumerate()
-                                    .filter(|(_, x)| *x == rule)
-                                    .next();
+        if let Some(start) = self.path.iter().position(|waiting| *waiting == idx) {
+            return Err(self.cycle(start));
+        }
 
-                if let Some((idx, _)) = violator {
-                    update.swap(pivot, idx);
+        self.path.push(idx);
 
-                    return fix_broken_rules(&update, rules);
-                }
+        for before in self.predecessors[idx].clone() {
+            if !self.placed[before] {
+                trace!("{} pulled ahead of {}", self.update[before], self.update[idx]);
             }
+
+            self.place(before)?;
         }
+
+        self.path.pop();
+        self.placed[idx] = true;
+        self.order.push(self.update[idx]);
+
+        Ok(())
     }
 
-    update
+    /// Each page on the path from `start` must follow the next, and the last must follow the first.
+    fn cycle(&self, start: usize) -> RuleCycle {
+        let mut cycle: Vec<usize> = self.path[start..].iter().rev().copied().collect();
+
+        // Start from the page that comes first in the update
+        let first = cycle.iter().position_min().unwrap_or(0);
+        cycle.rotate_left(first);
+
+        RuleCycle { pages: cycle.into_iter().map(|idx| self.update[idx]).collect() }
+    }
 }
 
 #[test]
 fn fix_broken_rule_update_4() {
     let order_items: Vec<(usize, usize)> = vec![(97, 75)];
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+    let order_rules = PageOrdering::from_rules(order_items).unwrap();
 
     let update: Vec<usize> = vec![75,97,47,61,53];
 
-    assert_eq!(vec![97,75,47,61,53], fix_broken_rules(&update, &order_rules));
+    assert_eq!(Ok(vec![97,75,47,61,53]), fix_broken_rules(&update, &order_rules));
 }
 
 #[test]
@@ -162,94 +526,43 @@ fn fix_broken_rule_update_6() {
     (75,13),
     (53,13),];
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+    let order_rules = PageOrdering::from_rules(order_items).unwrap();
 
     let update: Vec<usize> = vec![97,13,75,29,47];
 
-    assert_eq!(vec![97,75,47,29,13], fix_broken_rules(&update, &order_rules));
+    assert_eq!(Ok(vec![97,75,47,29,13]), fix_broken_rules(&update, &order_rules));
 }
 
 #[test]
 fn find_broken_rule_sum_input() {
-    let (order_items, updates) = load_data_for_token("input");
-
-    let order_rules = order_items.into_iter()
-            .into_group_map();
-
-    let fixed_rules : Vec<Vec<usize>> = updates.iter().enumerate()
-            .filter(|(_idx, update)| !update_is_in_order(update, &order_rules))
-            .map(|(_, update)| fix_broken_rules(update, &order_rules))
-            .collect();
-
-    println!("Broken rules: {}", fixed_rules.len());
-
-    dbg!(&fixed_rules);
-
-    let middle_page_sum : u32 = fixed_rules.into_iter()
-        .map(|v| {
-            let l = &v.len();
-
-            v.get( (l / 2) ).unwrap().clone()
-        } )
-        .map(|v| v as u32 )
-        .sum();
-
-    assert_eq!(123, middle_page_sum);
+    assert_eq!(Answer::Unsigned(4655), load_data_for_token("input").part2());
 }
 
-
 #[test]
 fn find_broken_rule_sum_test() {
-    let (order_items, updates) = load_data_for_token("test");
-
-    let order_rules = order_items.into_iter()
-            .into_group_map();
-
-    let fixed_rules : Vec<Vec<usize>> = updates.iter().enumerate()
-            .filter(|(_idx, update)| !update_is_in_order(update, &order_rules))
-            .map(|(_, update)| fix_broken_rules(update, &order_rules))
-            .collect();
-
-    println!("Broken rules: {}", fixed_rules.len());
-
-    dbg!(&fixed_rules);
-
-    let middle_page_sum : u32 = fixed_rules.into_iter()
-        .map(|v| {
-            let l = &v.len();
-
-            v.get( (l / 2) ).unwrap().clone()
-        } )
-        .map(|v| v as u32 )
-        .sum();
-
-    assert_eq!(123, middle_page_sum);
+    assert_eq!(Answer::Unsigned(123), load_data_for_token("test").part2());
 }
 
-
 #[test]
 fn test_update_4() {
     let order_items: Vec<(usize, usize)> = vec![(97, 75)];
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+    let order_rules = PageOrdering::from_rules(order_items).unwrap();
 
     let update: Vec<usize> = vec![75,97,47,61,53];
 
-    assert_eq!(false, update_is_in_order(&update, &order_rules));
+    assert!(!update_is_in_order(&update, &order_rules));
 }
 
 #[test]
 fn test_update_5() {
     let order_items: Vec<(usize, usize)> = vec![(29, 13)];
 
-    let order_rules = order_items.into_iter()
-            .into_group_map();
+    let order_rules = PageOrdering::from_rules(order_items).unwrap();
 
     let update: Vec<usize> = vec![61, 13, 29];
 
-    assert_eq!(false, update_is_in_order(&update, &order_rules));
+    assert!(!update_is_in_order(&update, &order_rules));
 }
 
 #[test]
@@ -263,17 +576,18 @@ fn test_updates_in_order() {
 
 #[test]
 fn build_order_lookups() {
-    let order_rules = read_order_lines("advent5a-test-order.txt");
+    let order_rules = read_order_lines(input::lines(&input::read_input("inputs/advent5a-test-order.txt").unwrap())).unwrap();
 
     let ordered_map = order_rules.into_iter()
         .into_group_map();
 
-    dbg!(ordered_map);
+    assert_eq!(6, ordered_map.len());
+    assert_eq!(5, ordered_map[&75].len());
 }
 
 #[test]
 fn load_order_lines() {
-    let order_rules = read_order_lines("advent5a-test-order.txt");
+    let order_rules = read_order_lines(input::lines(&input::read_input("inputs/advent5a-test-order.txt").unwrap())).unwrap();
 
     assert_eq!(21, order_rules.len());
 
@@ -282,9 +596,196 @@ fn load_order_lines() {
 
 #[test]
 fn load_update_lines() {
-    let updates = read_update_lines("advent5a-test-update.txt");
+    let updates = read_update_lines(input::lines(&input::read_input("inputs/advent5a-test-update.txt").unwrap())).unwrap();
 
     dbg!(&updates);
 
     assert_eq!(6, updates.len());
-}
\ No newline at end of file
+}
+
+#[test]
+fn fix_broken_rules_keeps_free_pages_in_place() {
+    let order_rules = PageOrdering::from_rules(vec![(3, 1)]).unwrap();
+
+    assert_eq!(Ok(vec![5, 3, 1, 4]), fix_broken_rules(&[5, 1, 3, 4], &order_rules));
+}
+
+#[test]
+fn fix_broken_rules_pulls_required_pages_forward() {
+    let order_rules = PageOrdering::from_rules(vec![(1, 3)]).unwrap();
+
+    assert_eq!(Ok(vec![1, 3, 9]), fix_broken_rules(&[3, 9, 1], &order_rules));
+    assert_eq!(Answer::Unsigned(3), Advent5::parse("1|3\n\n3,9,1").unwrap().part2());
+}
+
+#[test]
+fn fix_broken_rules_reports_cycle() {
+    let order_rules = PageOrdering::from_rules(vec![(1, 2), (2, 3), (3, 1), (4, 1)]).unwrap();
+
+    let cycle = fix_broken_rules(&[4, 1, 2, 3], &order_rules).unwrap_err();
+
+    assert_eq!(3, cycle.pages.len());
+    assert!(cycle.to_string().starts_with("ordering rules form a cycle: "));
+
+    for (before, after) in cycle.pages.iter().zip(cycle.pages.iter().cycle().skip(1)) {
+        assert!(order_rules.must_precede(*before, *after));
+    }
+}
+
+#[test]
+fn update_with_cycle_is_only_left_out_of_part2() {
+    let advent5 = Advent5::parse("1|2\n2|3\n3|1\n\n1,2\n1,2,3\n4,2,1").unwrap();
+
+    assert_eq!(Answer::Unsigned(2), advent5.part1());
+    assert_eq!(Answer::Unsigned(1), advent5.part2());
+
+    let cycle = fix_broken_rules(&advent5.updates()[1], advent5.rules()).unwrap_err();
+    assert_eq!("ordering rules form a cycle: 1 -> 2 -> 3 -> 1", cycle.to_string());
+}
+
+#[test]
+fn rule_violations_lists_every_broken_rule() {
+    let order_rules = PageOrdering::from_rules(vec![(97, 75), (97, 47), (75, 47), (61, 53)]).unwrap();
+
+    let violations = rule_violations(&[47, 75, 97, 61, 53], &order_rules);
+
+    assert_eq!(
+        vec![
+            Violation { before: 75, after: 47, before_position: 1, after_position: 0 },
+            Violation { before: 97, after: 47, before_position: 2, after_position: 0 },
+            Violation { before: 97, after: 75, before_position: 2, after_position: 1 },
+        ],
+        violations
+    );
+    assert_eq!("rule 75|47 broken: 75 at position 2 comes after 47 at position 1", violations[0].to_string());
+}
+
+#[test]
+fn audit_test_updates() {
+    let advent5 = load_data_for_token("test");
+
+    let broken: Vec<usize> = advent5.audit().updates.iter().map(|audit| audit.violations.len()).collect();
+
+    assert_eq!(vec![0, 0, 0, 1, 1, 4], broken);
+}
+
+#[test]
+fn audit_reports_update_with_cycle() {
+    let advent5 = Advent5::parse("1|2\n2|3\n3|1\n\n1,2\n1,2,3").unwrap();
+
+    let expected = "\
+update 1: 1,2: in order
+update 2: 1,2,3: 1 rule(s) broken
+  rule 3|1 broken: 3 at position 3 comes after 1 at position 1
+  cannot be reordered: ordering rules form a cycle: 1 -> 2 -> 3 -> 1
+1 of 2 updates out of order
+";
+
+    assert_eq!(expected, advent5.audit().to_string());
+}
+
+#[test]
+fn page_ordering_lookups() {
+    let rules = PageOrdering::from_rules(vec![(47, 53), (97, 13), (97, 47), (47, 53)]).unwrap();
+
+    assert_eq!(3, rules.len());
+    assert!(rules.must_precede(97, 47));
+    assert!(!rules.must_precede(47, 97));
+    assert!(!rules.must_precede(13, 53));
+    assert_eq!(&[13, 47], rules.successors(97));
+    assert!(rules.successors(13).is_empty());
+}
+
+#[test]
+fn page_ordering_conflicts() {
+    let mut rules = PageOrdering::new();
+
+    assert_eq!(Ok(()), rules.insert(1, 2));
+    assert_eq!(Err(RuleConflict::Duplicate { before: 1, after: 2 }), rules.insert(1, 2));
+    assert_eq!(Err(RuleConflict::Contradiction { before: 2, after: 1 }), rules.insert(2, 1));
+    assert_eq!(Err(RuleConflict::Duplicate { before: 2, after: 1 }), rules.insert(2, 1));
+    assert_eq!(2, rules.len());
+    assert_eq!("rule 2|1 contradicts the rule 1|2", RuleConflict::Contradiction { before: 2, after: 1 }.to_string());
+
+    assert_eq!(2, PageOrdering::from_rules(vec![(1, 2), (2, 1)]).unwrap().len());
+
+    assert_eq!(Err(RuleConflict::SelfRule { page: 3 }), rules.insert(3, 3));
+    assert_eq!(Err(RuleConflict::SelfRule { page: 3 }), PageOrdering::from_rules(vec![(3, 3), (3, 3)]).map(|_| ()));
+    assert_eq!(2, rules.len());
+
+    let err = Advent5::parse("1|2\n1|1\n1|1\n\n1,2").err().unwrap();
+    assert_eq!("<input>:2:1: rules section: rule 1|1 puts a page before itself", err.to_string());
+}
+
+#[test]
+fn page_ordering_comparator_sorts_updates() {
+    let Advent5 { order_rules, updates } = load_data_for_token("test");
+
+    for update in updates {
+        let compare = order_rules.comparator(&update).unwrap();
+
+        for (a, b) in update.iter().tuple_combinations().flat_map(|(a, b)| [(a, b), (b, a)]) {
+            if order_rules.must_precede(*a, *b) {
+                assert_eq!(Ordering::Less, compare(a, b));
+                assert_eq!(Ordering::Greater, compare(b, a));
+            }
+        }
+
+        let mut sorted = update.clone();
+        sorted.sort_by(&compare);
+
+        assert!(update_is_in_order(&sorted, &order_rules));
+    }
+}
+
+#[test]
+fn page_ordering_comparator_is_total_for_partial_rules() {
+    let order_rules = PageOrdering::from_rules(vec![(3, 1), (4, 2)]).unwrap();
+    let update = [5, 1, 2, 3, 4];
+
+    let compare = order_rules.comparator(&update).unwrap();
+
+    let mut sorted = update;
+    sorted.sort_by(&compare);
+
+    assert_eq!([5, 3, 1, 4, 2], sorted);
+    assert_eq!(Ordering::Less, compare(&5, &2));
+    assert_eq!(Ordering::Less, compare(&2, &6));
+
+    let cyclic = PageOrdering::from_rules(vec![(1, 2), (2, 3), (3, 1)]).unwrap();
+    assert!(cyclic.comparator(&[1, 2, 3]).is_err());
+}
+
+#[test]
+fn two_file_form_matches_combined() {
+    for token in ["test", "input"] {
+        let combined = load_data_for_token(token);
+        let split = Advent5::from_files(
+            &format!("inputs/advent5a-{token}-order.txt"),
+            &format!("inputs/advent5a-{token}-update.txt"),
+        )
+        .unwrap();
+
+        assert_eq!(combined.rules().rules(), split.rules().rules());
+        assert_eq!(combined.updates(), split.updates());
+    }
+}
+
+#[test]
+fn parse_tolerates_extra_blank_lines() {
+    let advent5 = Advent5::parse("1|2\n\n\n2,1\n1,2\n\n").unwrap();
+
+    assert_eq!(&[vec![2, 1], vec![1, 2]], advent5.updates());
+}
+
+#[test]
+fn errors_name_their_section() {
+    let error = |input: &str| Advent5::parse(input).err().unwrap().to_string();
+
+    assert_eq!("<input>:2:1: rules section: invalid number `x`", error("1|2\nx|3\n\n1,2"));
+    assert_eq!("<input>:2:4: rules section: expected `|` followed by a page number", error("1|2\n3,4\n\n1,2"));
+    assert_eq!("<input>:5:3: updates section: invalid number `x`", error("1|2\n\n1,2\n\n2,x"));
+    assert_eq!("<input>:4:1: updates section: ordering rule after the blank line that ends the rules", error("1|2\n\n1,2\n3|4"));
+    assert_eq!("<input>: updates section: no updates; they follow the rules after a blank line", error("1|2\n3|4"));
+    assert_eq!("<input>: updates section: no updates; they follow the rules after a blank line", error("1|2\n\n\n"));
+}
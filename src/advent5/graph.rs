@@ -0,0 +1,283 @@
+use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Write};
+
+use super::PageOrdering;
+#[cfg(test)]
+use crate::solution::Solution;
+
+/// Tarjan's algorithm over the rule graph.
+struct Tarjan<'a> {
+    rules: &'a PageOrdering,
+    index: HashMap<usize, usize>,
+    low_link: HashMap<usize, usize>,
+    stack: Vec<usize>,
+    on_stack: BTreeSet<usize>,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, page: usize) {
+        let index = self.index.len();
+
+        self.index.insert(page, index);
+        self.low_link.insert(page, index);
+        self.stack.push(page);
+        self.on_stack.insert(page);
+
+        for next in self.rules.successors(page) {
+            if !self.index.contains_key(next) {
+                self.visit(*next);
+                self.low_link.insert(page, self.low_link[&page].min(self.low_link[next]));
+            } else if self.on_stack.contains(next) {
+                self.low_link.insert(page, self.low_link[&page].min(self.index[next]));
+            }
+        }
+
+        if self.low_link[&page] == index {
+            let mut component = vec![];
+
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(&member);
+                component.push(member);
+
+                if member == page {
+                    break;
+                }
+            }
+
+            component.sort();
+            self.components.push(component);
+        }
+    }
+}
+
+/// The strongly connected components of the rule graph, each sorted, ordered by their smallest page.
+///
+/// Any component with more than one page is a set of rules that no single global order can satisfy.
+pub fn strongly_connected_components(rules: &PageOrdering) -> Vec<Vec<usize>> {
+    let mut tarjan = Tarjan {
+        rules,
+        index: HashMap::new(),
+        low_link: HashMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        components: vec![],
+    };
+
+    for page in rules.pages() {
+        if !tarjan.index.contains_key(&page) {
+            tarjan.visit(page);
+        }
+    }
+
+    tarjan.components.sort();
+    tarjan.components
+}
+
+pub fn is_dag(rules: &PageOrdering) -> bool {
+    strongly_connected_components(rules).iter()
+        .all(|component| component.len() == 1 && !rules.must_precede(component[0], component[0]))
+}
+
+fn reachable_avoiding(rules: &PageOrdering, from: usize, to: usize, skip_edge: (usize, usize)) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![from];
+
+    while let Some(page) = pending.pop() {
+        for next in rules.successors(page) {
+            if (page, *next) == skip_edge || !seen.insert(*next) {
+                continue;
+            }
+
+            if *next == to {
+                return true;
+            }
+
+            pending.push(*next);
+        }
+    }
+
+    false
+}
+
+/// The fewest rules implying the same order, or `None` if the rules contain a cycle and so have no unique reduction.
+pub fn transitive_reduction(rules: &PageOrdering) -> Option<Vec<(usize, usize)>> {
+    if !is_dag(rules) {
+        return None;
+    }
+
+    Some(
+        rules.rules().into_iter()
+            .filter(|(before, after)| !reachable_avoiding(rules, *before, *after, (*before, *after)))
+            .collect(),
+    )
+}
+
+/// Graphviz DOT for the rules, drawing pages that sit on a cycle in red with one cluster per cyclic component.
+pub fn to_dot(rules: &PageOrdering) -> String {
+    let mut out = String::from("digraph rules {\n    rankdir=LR;\n");
+
+    for (idx, component) in strongly_connected_components(rules).iter().enumerate() {
+        if component.len() > 1 {
+            writeln!(out, "    subgraph cluster_{idx} {{\n        label=\"cycle\";\n        color=red;").unwrap();
+
+            for page in component {
+                writeln!(out, "        {page} [color=red];").unwrap();
+            }
+
+            out.push_str("    }\n");
+        } else {
+            writeln!(out, "    {};", component[0]).unwrap();
+        }
+    }
+
+    for (before, after) in rules.rules() {
+        writeln!(out, "    {before} -> {after};").unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// What the `graph` command reports about a set of rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphReport {
+    pub rules: usize,
+    pub pages: usize,
+    pub components: Vec<Vec<usize>>,
+    /// `None` when the rules contain a cycle.
+    pub reduction: Option<Vec<(usize, usize)>>,
+}
+
+impl GraphReport {
+    pub fn is_dag(&self) -> bool {
+        self.reduction.is_some()
+    }
+
+    /// The components with more than one page, each a set of rules no order can satisfy.
+    pub fn cycles(&self) -> impl Iterator<Item = &Vec<usize>> {
+        self.components.iter().filter(|component| component.len() > 1)
+    }
+}
+
+impl Display for GraphReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} rules over {} pages", self.rules, self.pages)?;
+        writeln!(f, "DAG: {}", if self.is_dag() { "yes" } else { "no" })?;
+        writeln!(f, "strongly connected components: {} ({} with a cycle)", self.components.len(), self.cycles().count())?;
+
+        for component in self.cycles() {
+            writeln!(f, "  cycle through {} pages: {}", component.len(), component.iter().join(","))?;
+        }
+
+        match &self.reduction {
+            Some(reduction) => writeln!(f, "transitive reduction: {} rules", reduction.len()),
+            None => writeln!(f, "transitive reduction: none, the rules contain a cycle"),
+        }
+    }
+}
+
+pub fn analyse(rules: &PageOrdering) -> GraphReport {
+    GraphReport {
+        rules: rules.len(),
+        pages: rules.pages().len(),
+        components: strongly_connected_components(rules),
+        reduction: transitive_reduction(rules),
+    }
+}
+
+#[cfg(test)]
+fn test_rules() -> PageOrdering {
+    super::load_data_for_token("test").order_rules
+}
+
+#[test]
+fn test_rules_are_a_dag() {
+    let rules = test_rules();
+
+    assert!(is_dag(&rules));
+    assert_eq!(7, strongly_connected_components(&rules).len());
+
+    // The test rules totally order their seven pages, so only the chain between neighbours remains
+    assert_eq!(
+        Some(vec![(97, 75), (75, 47), (47, 61), (61, 53), (53, 29), (29, 13)].into_iter().collect::<BTreeSet<_>>()),
+        transitive_reduction(&rules).map(|r| r.into_iter().collect())
+    );
+}
+
+#[test]
+fn cyclic_rules() {
+    let rules = PageOrdering::from_rules(vec![(1, 2), (2, 3), (3, 1), (3, 4)]).unwrap();
+
+    assert!(!is_dag(&rules));
+    assert_eq!(vec![vec![1, 2, 3], vec![4]], strongly_connected_components(&rules));
+    assert_eq!(None, transitive_reduction(&rules));
+
+    let restricted = rules.restricted_to(&[1, 2, 4]);
+
+    assert!(is_dag(&restricted));
+    assert_eq!(Some(vec![(1, 2)]), transitive_reduction(&restricted));
+}
+
+#[test]
+fn input_rules_only_order_within_updates() {
+    let advent5 = super::load_data_for_token("input");
+
+    assert!(!is_dag(advent5.rules()));
+
+    for update in advent5.updates() {
+        assert!(is_dag(&advent5.rules().restricted_to(update)));
+    }
+}
+
+#[test]
+fn dot_export() {
+    let rules = PageOrdering::from_rules(vec![(1, 2), (2, 3), (3, 1), (3, 4)]).unwrap();
+
+    let dot = to_dot(&rules);
+
+    assert!(dot.starts_with("digraph rules {\n"));
+    assert!(dot.contains("subgraph cluster_0 {"));
+    assert!(dot.contains("        2 [color=red];\n"));
+    assert!(dot.contains("    4;\n"));
+    assert!(dot.contains("    3 -> 4;\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn update_with_cycle_can_be_audited_and_graphed() {
+    let advent5 = super::Advent5::parse("1|2\n2|3\n3|1\n3|4\n\n1,2\n4,1,2,3").unwrap();
+
+    let audit = advent5.audit();
+
+    assert_eq!(None, audit.updates[0].cycle);
+    assert_eq!(Some(vec![1, 2, 3]), audit.updates[1].cycle.as_ref().map(|cycle| cycle.pages.clone()));
+
+    let expected = "\
+4 rules over 4 pages
+DAG: no
+strongly connected components: 2 (1 with a cycle)
+  cycle through 3 pages: 1,2,3
+transitive reduction: none, the rules contain a cycle
+";
+
+    assert_eq!(expected, analyse(&advent5.rules().restricted_to(&advent5.updates()[1])).to_string());
+    assert!(!analyse(advent5.rules()).is_dag());
+    assert!(analyse(&advent5.rules().restricted_to(&advent5.updates()[0])).is_dag());
+}
+
+#[test]
+fn contradicting_rules_can_be_audited_and_graphed() {
+    let advent5 = super::Advent5::parse("1|2\n3|4\n2|1\n\n1,2\n3,4").unwrap();
+
+    let audit = advent5.audit();
+
+    assert_eq!(Some(vec![1, 2]), audit.updates[0].cycle.as_ref().map(|cycle| cycle.pages.clone()));
+    assert_eq!(None, audit.updates[1].cycle);
+
+    let report = analyse(advent5.rules());
+
+    assert!(!report.is_dag());
+    assert_eq!(vec![&vec![1, 2]], report.cycles().collect::<Vec<_>>());
+}
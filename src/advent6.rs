@@ -1,165 +1,152 @@
-use itertools::{iproduct, Itertools};
-use regex::Regex;
-use std::{
-    collections::HashMap,
-    fs::read_to_string,
-    io::Error,
-    ops::{Index, IndexMut},
-    slice::RChunks,
-};
+pub mod render;
+
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::bitset::BitSet;
+use crate::geometry::{Direction4, Point};
+use crate::grid::Grid;
+use crate::input::{InputError, UNNAMED_INPUT};
+use crate::solution::{Answer, Solution};
 
 #[derive(PartialEq, Debug, Clone, Copy)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+pub enum MazeCell {
+    Open,
+    Blocked,
+    Guard(Direction4),
 }
 
-impl Direction {
-    fn move_offset(&self) -> Position {
-        match self {
-            Direction::Up => Position::new_i32(0, -1),
-            Direction::Down => Position::new_i32(0, 1),
-            Direction::Left => Position::new_i32(-1, 0),
-            Direction::Right => Position::new_i32(1, 0),
-        }
-    }
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct GuardState {
+    pub position: Point,
+    pub facing: Direction4,
+}
 
-    fn rotate(&self) -> Direction {
-        match self {
-            Direction::Up => Direction::Right,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-            Direction::Right => Direction::Down,
+impl MazeCell {
+    pub fn from_char(c: char) -> Option<MazeCell> {
+        match c {
+            '.' => Some(MazeCell::Open),
+            '#' => Some(MazeCell::Blocked),
+            '^' => Some(MazeCell::Guard(Direction4::Up)),
+            'v' => Some(MazeCell::Guard(Direction4::Down)),
+            '>' => Some(MazeCell::Guard(Direction4::Right)),
+            '<' => Some(MazeCell::Guard(Direction4::Left)),
+            _ => None,
         }
     }
 }
 
-#[derive(PartialEq, Debug, Clone, Copy)]
-enum MazeCell {
-    Open,
-    Blocked,
-    Guard(Direction),
+pub type Maze = Grid<MazeCell>;
+
+pub fn is_position_valid(m: &Maze, p: &Point) -> bool {
+    m.in_bounds(*p)
 }
 
-#[derive(PartialEq, Clone, Copy, Debug)]
-struct Position {
-    pub x: i32,
-    pub y: i32,
+/// Extra characters to accept in a maze on top of the standard `.`, `#` and guard arrows.
+#[derive(Clone, Debug, Default)]
+pub struct Legend {
+    extra: HashMap<char, MazeCell>,
 }
 
-impl Position {
-    fn new(x: usize, y: usize) -> Position {
-        Position {
-            x: x as i32,
-            y: y as i32,
-        }
+impl Legend {
+    pub fn new() -> Legend {
+        Legend::default()
     }
 
-    fn new_i32(x: i32, y: i32) -> Position {
-        Position { x: x, y: y }
+    pub fn with(mut self, c: char, cell: MazeCell) -> Legend {
+        self.extra.insert(c, cell);
+        self
     }
 
-    fn add(&self, other: Position) -> Position {
-        Position {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
+    pub fn cell(&self, c: char) -> Option<MazeCell> {
+        self.extra.get(&c).copied().or_else(|| MazeCell::from_char(c))
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-struct GuardState {
-    pub position: Position,
-    pub facing: Direction,
+pub fn read_maze_lines(contents: &str) -> Result<Maze, InputError> {
+    read_maze_with_legend(contents, &Legend::default())
 }
 
-impl MazeCell {
-    fn from_char(c: char) -> MazeCell {
-        match c {
-            '.' => MazeCell::Open,
-            '#' => MazeCell::Blocked,
-            '^' => MazeCell::Guard(Direction::Up),
-            'v' => MazeCell::Guard(Direction::Down),
-            '>' => MazeCell::Guard(Direction::Right),
-            '<' => MazeCell::Guard(Direction::Left),
-            _ => {
-                panic!("Unknown maze cell character: {}", c)
-            }
-        }
+/// Parse a rectangular maze, rejecting unknown characters and ragged rows with their line and column.
+pub fn read_maze_with_legend(contents: &str, legend: &Legend) -> Result<Maze, InputError> {
+    let maze = Grid::parse(contents, |c| legend.cell(c))?;
+
+    if maze.width() == 0 {
+        return Err(InputError::invalid("maze is empty"));
     }
+
+    Ok(maze)
 }
 
-type Maze = Vec<Vec<MazeCell>>;
+/// Parse a maze and find its guard, of which there must be exactly one.
+pub fn parse_maze(contents: &str, legend: &Legend) -> Result<(Maze, GuardState), InputError> {
+    let maze = read_maze_with_legend(contents, legend)?;
 
-fn is_position_valid(m: &Maze, p: &Position) -> bool {
-    if p.x < 0 || p.y < 0 || p.y as usize >= m.len() || p.x as usize >= m[0].len() {
-        false
-    } else {
-        true
+    let guards = find_guards(&maze);
+
+    match guards.as_slice() {
+        [] => Err(InputError::invalid("no guard found in maze")),
+        [guard] => Ok((maze, *guard)),
+        [first, second, ..] => Err(InputError::Parse {
+            file: UNNAMED_INPUT.to_string(),
+            line: second.position.y as usize + 1,
+            column: second.position.x as usize + 1,
+            message: format!("second guard found; the first is at line {}, column {}", first.position.y + 1, first.position.x + 1),
+        }),
     }
 }
 
-fn read_maze_lines(filename: &str) -> Maze {
-    read_to_string(filename)
-        .unwrap() // panic on possible file-reading errors
-        .lines() // split the string into an iterator of string slices
-        .map(|s| s.chars().into_iter().map(MazeCell::from_char).collect())
+/// Every guard in the maze, row by row.
+pub fn find_guards(m: &Maze) -> Vec<GuardState> {
+    m.iter()
+        .filter_map(|(position, cell)| match cell {
+            MazeCell::Guard(facing) => Some(GuardState { position, facing: *facing }),
+            _ => None,
+        })
         .collect()
 }
 
-fn find_guard(m: &Maze) -> GuardState {
-    for (y, row) in m.into_iter().enumerate() {
-        for (x, cell) in row.into_iter().enumerate() {
-            if let MazeCell::Guard(d) = cell {
-                return GuardState {
-                    position: Position::new(x, y),
-                    facing: *d,
-                };
-            }
-        }
-    }
-
-    panic!("Guard not found")
+pub fn find_guard(m: &Maze) -> Option<GuardState> {
+    m.iter().find_map(|(position, cell)| match cell {
+        MazeCell::Guard(d) => Some(GuardState {
+            position,
+            facing: *d,
+        }),
+        _ => None,
+    })
 }
 
-fn move_guard_single(m: &mut Maze, guard: &GuardState) -> Option<GuardState> {
+pub fn move_guard_single(m: &Maze, guard: &GuardState) -> Option<GuardState> {
     if !is_position_valid(m, &guard.position) {
         //Guard is already off the board, they cannot move further
         return None;
     }
 
-    let offset = guard.facing.move_offset();
+    let offset = guard.facing.offset();
 
-    let provisional_position = guard.position.add(offset);
+    let provisional_position = guard.position + offset;
 
-    if !is_position_valid(m, &provisional_position) {
+    match m.get(provisional_position) {
         //Position is off the board, return it.
-        return Some(GuardState {
-            position: provisional_position,
-            facing: guard.facing,
-        });
-    }
-
-    match m[provisional_position.y as usize][provisional_position.x as usize] {
-        MazeCell::Open | MazeCell::Guard(_) => Some(GuardState {
+        None | Some(MazeCell::Open) | Some(MazeCell::Guard(_)) => Some(GuardState {
             position: provisional_position,
             facing: guard.facing,
         }),
-        MazeCell::Blocked => None,
+        Some(MazeCell::Blocked) => None,
     }
 }
 
-fn move_guard_until_stop(
-    m: &mut Maze,
+/// Walk the guard forward until it hits a barrier or leaves the maze, returning the states and new cells visited.
+pub fn move_guard_until_stop(
+    m: &Maze,
     guard_states: &mut Vec<GuardState>,
 ) -> (Vec<GuardState>, u32) {
     let mut moves = 0;
 
-    while let Some(new_state) = move_guard_single(m, &guard_states.last().unwrap()) {
+    while let Some(new_state) = move_guard_single(m, guard_states.last().unwrap()) {
         if !guard_states
-            .into_iter()
+            .iter()
             .any(|s| s.position == new_state.position)
         {
             moves += 1;
@@ -172,16 +159,16 @@ fn move_guard_until_stop(
 }
 
 fn useful_barrier_location(
-    m: &mut Maze,
-    guard_states: &Vec<GuardState>,
-    extrapolated_states: &Vec<GuardState>,
+    m: &Maze,
+    guard_states: &[GuardState],
+    extrapolated_states: &[GuardState],
     new_state: &GuardState,
-) -> Option<Position> {
+) -> Option<Point> {
     //Evaluate the position to the right for the presence of a previous position, facing rotated 90 from current
     //If so, a barrier directly ahead would cause a loop
-    let rotated_facing = new_state.facing.rotate();
+    let rotated_facing = new_state.facing.turn_right();
     let possible_previous = GuardState {
-        position: new_state.position.add(rotated_facing.move_offset()),
+        position: new_state.position + rotated_facing.offset(),
         facing: rotated_facing,
     };
 
@@ -189,27 +176,26 @@ fn useful_barrier_location(
         && (guard_states.contains(&possible_previous) || extrapolated_states.contains(&possible_previous))
     {
         //In this case, see if we can place a barrier ahead of ourselves
-        let barrier_position = new_state.position.add(new_state.facing.move_offset());
+        let barrier_position = new_state.position + new_state.facing.offset();
 
         if is_position_valid(m, &barrier_position) {
             return Some(barrier_position);
         }
     }
 
-    return None;
+    None
 }
 
-fn extrapolate_backwards(m: &mut Maze, guard_states: &Vec<GuardState>, extrapolated_states: &Vec<GuardState>) -> Vec<GuardState> {
+fn extrapolate_backwards(m: &Maze, guard_states: &[GuardState], extrapolated_states: &[GuardState]) -> Vec<GuardState> {
     //Extrapolate the newest state backwards until there is a barrier.  Note that the facing must remain the same.
     let last_state = guard_states.last().unwrap();
-    let offset = last_state.facing.move_offset();
-    let reverse_offset = Position::new_i32(-offset.x, -offset.y);
+    let reverse_offset = -last_state.facing.offset();
     let mut states: Vec<GuardState> = vec![];
 
-    let mut potential_position = last_state.position.add(reverse_offset);
+    let mut potential_position = last_state.position + reverse_offset;
 
-    while is_position_valid(m, &potential_position) {
-        match m[potential_position.y as usize][potential_position.x as usize] {
+    while let Some(cell) = m.get(potential_position) {
+        match cell {
             MazeCell::Blocked => {
                 break;
             }
@@ -225,7 +211,7 @@ fn extrapolate_backwards(m: &mut Maze, guard_states: &Vec<GuardState>, extrapola
                     break;
                 }
 
-                potential_position = potential_position.add(reverse_offset);
+                potential_position += reverse_offset;
             }
         }
     }
@@ -233,8 +219,12 @@ fn extrapolate_backwards(m: &mut Maze, guard_states: &Vec<GuardState>, extrapola
     states
 }
 
-fn move_guard_until_exit(m: &mut Maze, guard: GuardState) -> (Vec<GuardState>, u32, Vec<Position>) {
-    let mut barrier_locations: Vec<Position> = vec![];
+/// Walk the guard until it leaves the maze.
+///
+/// Returns every state visited, the number of distinct cells entered and the barrier locations that would cause a loop.
+/// The barrier locations come from a heuristic that misses many loops; [`loop_obstructions`] gives the exact set.
+pub fn move_guard_until_exit(m: &Maze, guard: GuardState) -> (Vec<GuardState>, u32, Vec<Point>) {
+    let mut barrier_locations: Vec<Point> = vec![];
     let mut guard_states = vec![guard];
     let mut moves = 0;
 
@@ -243,7 +233,7 @@ fn move_guard_until_exit(m: &mut Maze, guard: GuardState) -> (Vec<GuardState>, u
     extrapolated_states.append(&mut extrapolate_backwards(m, &guard_states, &extrapolated_states));
 
     while is_position_valid(m, &guard_states.last().unwrap().position) {
-        while let Some(new_state) = move_guard_single(m, &guard_states.last().unwrap()) {
+        while let Some(new_state) = move_guard_single(m, guard_states.last().unwrap()) {
             if !guard_states
                 .clone()
                 .into_iter()
@@ -263,7 +253,7 @@ fn move_guard_until_exit(m: &mut Maze, guard: GuardState) -> (Vec<GuardState>, u
             if is_position_valid(m, &guard_states.last().unwrap().position) {
                 let rotated_state = GuardState {
                     position: last_state.position,
-                    facing: last_state.facing.rotate(),
+                    facing: last_state.facing.turn_right(),
                 };
 
                 if let Some(barrier_position) = useful_barrier_location(m, &guard_states, &extrapolated_states, &rotated_state) {
@@ -282,13 +272,493 @@ fn move_guard_until_exit(m: &mut Maze, guard: GuardState) -> (Vec<GuardState>, u
     (guard_states, moves, barrier_locations)
 }
 
+/// How a guard's walk through the maze ends.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum WalkOutcome {
+    Exits,
+    Loops,
+}
+
+/// Which way a guard turns when the cell ahead is blocked.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum TurnRule {
+    #[default]
+    Right,
+    Left,
+    Reverse,
+}
+
+impl TurnRule {
+    pub fn turn(&self, facing: Direction4) -> Direction4 {
+        match self {
+            TurnRule::Right => facing.turn_right(),
+            TurnRule::Left => facing.turn_left(),
+            TurnRule::Reverse => facing.reverse(),
+        }
+    }
+}
+
+/// Advance the guard one step, turning in place by `rule` when the cell ahead is blocked or is the `obstruction`.
+fn step_guard(m: &Maze, guard: &GuardState, obstruction: Option<Point>, rule: TurnRule) -> GuardState {
+    let ahead = guard.position + guard.facing.offset();
+
+    if Some(ahead) == obstruction || m.get(ahead) == Some(&MazeCell::Blocked) {
+        GuardState {
+            position: guard.position,
+            facing: rule.turn(guard.facing),
+        }
+    } else {
+        GuardState {
+            position: ahead,
+            facing: guard.facing,
+        }
+    }
+}
+
+/// Walk the guard with an optional extra barrier, stopping once it leaves the maze or repeats a state.
+pub fn walk_guard(m: &Maze, guard: GuardState, obstruction: Option<Point>) -> WalkOutcome {
+    guard_trail(m, guard, obstruction).1
+}
+
+/// Every in-maze state of the walk made by [`walk_guard`], in order, along with how it ends.
+pub fn guard_trail(m: &Maze, guard: GuardState, obstruction: Option<Point>) -> (Vec<GuardState>, WalkOutcome) {
+    let mut stepper = GuardStepper::new(m, guard, obstruction);
+    let trail = stepper.by_ref().collect();
+
+    (trail, stepper.outcome().unwrap_or_else(|| unreachable!("stepper ran to completion")))
+}
+
+/// Replays a guard's walk one move at a time, yielding the starting state and then every step or turn.
+///
+/// Iteration ends when the guard leaves the maze or is about to repeat a state; [`GuardStepper::outcome`] says which.
+pub struct GuardStepper<'a> {
+    maze: &'a Maze,
+    obstruction: Option<Point>,
+    rule: TurnRule,
+    next: GuardState,
+    /// States already yielded, indexed like [`JumpTable`] entries: cell then direction.
+    seen: BitSet,
+    outcome: Option<WalkOutcome>,
+}
+
+impl<'a> GuardStepper<'a> {
+    pub fn new(maze: &'a Maze, guard: GuardState, obstruction: Option<Point>) -> GuardStepper<'a> {
+        let seen = BitSet::new(maze.width() * maze.height() * 4);
+
+        GuardStepper { maze, obstruction, rule: TurnRule::Right, next: guard, seen, outcome: None }
+    }
+
+    pub fn with_rule(mut self, rule: TurnRule) -> GuardStepper<'a> {
+        self.rule = rule;
+        self
+    }
+
+    /// How the walk ended, once the stepper is exhausted.
+    pub fn outcome(&self) -> Option<WalkOutcome> {
+        self.outcome
+    }
+
+    /// The state the guard returned to, once the stepper has found a loop.
+    pub fn loop_start(&self) -> Option<GuardState> {
+        (self.outcome == Some(WalkOutcome::Loops)).then_some(self.next)
+    }
+}
+
+impl Iterator for GuardStepper<'_> {
+    type Item = GuardState;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.outcome.is_some() {
+            return None;
+        }
+
+        let state = self.next;
+
+        if !is_position_valid(self.maze, &state.position) {
+            self.outcome = Some(WalkOutcome::Exits);
+            return None;
+        }
+
+        let cell = state.position.y as usize * self.maze.width() + state.position.x as usize;
+
+        if !self.seen.insert(cell * 4 + state.facing as usize) {
+            self.outcome = Some(WalkOutcome::Loops);
+            return None;
+        }
+
+        self.next = step_guard(self.maze, &state, self.obstruction, self.rule);
+
+        Some(state)
+    }
+}
+
+/// One guard's part in [`simulate_guards`].
+#[derive(PartialEq, Debug, Clone)]
+pub struct GuardReport {
+    pub start: GuardState,
+    /// Distinct cells the guard entered, its starting cell included.
+    pub visited: usize,
+    pub outcome: WalkOutcome,
+}
+
+/// Two or more guards standing on the same cell after the same number of moves.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Collision {
+    pub tick: usize,
+    pub position: Point,
+    /// Indexes into [`SimulationReport::guards`].
+    pub guards: Vec<usize>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct SimulationReport {
+    pub guards: Vec<GuardReport>,
+    pub collisions: Vec<Collision>,
+}
+
+/// A guard's walk, with looping guards continuing round their loop forever.
+struct Walk {
+    trail: Vec<GuardState>,
+    loop_start: Option<usize>,
+}
+
+impl Walk {
+    fn position_at(&self, tick: usize) -> Option<Point> {
+        match (self.trail.get(tick), self.loop_start) {
+            (Some(state), _) => Some(state.position),
+            (None, Some(start)) => Some(self.trail[start + (tick - start) % (self.trail.len() - start)].position),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Walk several guards at once, one move each per tick, all following `rule`.
+///
+/// Guards pass through each other. Collisions are reported until every guard has left the maze or completed one lap
+/// of its loop.
+pub fn simulate_guards(m: &Maze, guards: &[GuardState], rule: TurnRule) -> SimulationReport {
+    let walks: Vec<Walk> = guards.iter()
+        .map(|guard| {
+            let mut stepper = GuardStepper::new(m, *guard, None).with_rule(rule);
+            let trail: Vec<GuardState> = stepper.by_ref().collect();
+            let loop_start = stepper.loop_start().and_then(|start| trail.iter().position(|s| *s == start));
+
+            Walk { trail, loop_start }
+        })
+        .collect();
+
+    let reports = guards.iter().zip(&walks)
+        .map(|(guard, walk)| GuardReport {
+            start: *guard,
+            visited: walk.trail.iter().map(|s| s.position).collect::<HashSet<_>>().len(),
+            outcome: if walk.loop_start.is_some() { WalkOutcome::Loops } else { WalkOutcome::Exits },
+        })
+        .collect();
+
+    let ticks = walks.iter().map(|w| w.trail.len()).max().unwrap_or(0);
+    let mut collisions = vec![];
+
+    for tick in 0..ticks {
+        let mut occupied: BTreeMap<Point, Vec<usize>> = BTreeMap::new();
+
+        for (idx, walk) in walks.iter().enumerate() {
+            if let Some(position) = walk.position_at(tick) {
+                occupied.entry(position).or_default().push(idx);
+            }
+        }
+
+        collisions.extend(occupied.into_iter()
+            .filter(|(_, guards)| guards.len() > 1)
+            .map(|(position, guards)| Collision { tick, position, guards }));
+    }
+
+    SimulationReport { guards: reports, collisions }
+}
+
+impl Display for SimulationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, guard) in self.guards.iter().enumerate() {
+            writeln!(
+                f,
+                "guard {idx} from ({}, {}) facing {:?}: {} cells visited, {}",
+                guard.start.position.x,
+                guard.start.position.y,
+                guard.start.facing,
+                guard.visited,
+                match guard.outcome {
+                    WalkOutcome::Exits => "exits",
+                    WalkOutcome::Loops => "loops",
+                }
+            )?;
+        }
+
+        for collision in &self.collisions {
+            writeln!(
+                f,
+                "collision at tick {} in ({}, {}) between guards {}",
+                collision.tick,
+                collision.position.x,
+                collision.position.y,
+                collision.guards.iter().join(", ")
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// For every open cell and direction, the cell where a guard walking that way stops: the last open cell before a
+/// barrier, or the first cell off the maze.
+///
+/// Stops are stored as a single coordinate along the direction of travel, so a temporary obstacle only has to
+/// rewrite the cells that run into it along its row and column.
+pub struct JumpTable {
+    width: i32,
+    height: i32,
+    blocked: BitSet,
+    stops: Vec<i32>,
+}
+
+/// Table entries overwritten by [`JumpTable::add_obstacle`], to hand back to [`JumpTable::restore`].
+pub struct JumpTablePatch {
+    obstacle: Option<usize>,
+    saved: Vec<(usize, i32)>,
+}
+
+impl JumpTable {
+    pub fn new(m: &Maze) -> JumpTable {
+        let (width, height) = (m.width() as i32, m.height() as i32);
+        let mut blocked = BitSet::new(m.width() * m.height());
+
+        for (p, cell) in m.iter() {
+            if *cell == MazeCell::Blocked {
+                blocked.insert((p.y * width + p.x) as usize);
+            }
+        }
+
+        let mut table = JumpTable { width, height, blocked, stops: vec![0; m.width() * m.height() * 4] };
+
+        for y in 0..height {
+            let mut stop = -1;
+            for x in 0..width {
+                stop = table.scan(Point::new(x, y), Direction4::Left, stop, x + 1);
+            }
+
+            let mut stop = width;
+            for x in (0..width).rev() {
+                stop = table.scan(Point::new(x, y), Direction4::Right, stop, x - 1);
+            }
+        }
+
+        for x in 0..width {
+            let mut stop = -1;
+            for y in 0..height {
+                stop = table.scan(Point::new(x, y), Direction4::Up, stop, y + 1);
+            }
+
+            let mut stop = height;
+            for y in (0..height).rev() {
+                stop = table.scan(Point::new(x, y), Direction4::Down, stop, y - 1);
+            }
+        }
+
+        table
+    }
+
+    /// Record `stop` for `p`, or if `p` is a barrier return the stop for the cells behind it.
+    fn scan(&mut self, p: Point, d: Direction4, stop: i32, behind_barrier: i32) -> i32 {
+        if self.is_blocked(p) {
+            behind_barrier
+        } else {
+            let index = self.index(p, d);
+            self.stops[index] = stop;
+            stop
+        }
+    }
+
+    fn cell_index(&self, p: Point) -> usize {
+        (p.y * self.width + p.x) as usize
+    }
+
+    fn cell_point(&self, cell: usize) -> Point {
+        Point::new(cell as i32 % self.width, cell as i32 / self.width)
+    }
+
+    fn index(&self, p: Point, d: Direction4) -> usize {
+        self.cell_index(p) * 4 + d as usize
+    }
+
+    pub fn in_bounds(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height
+    }
+
+    pub fn is_blocked(&self, p: Point) -> bool {
+        self.blocked.contains(self.cell_index(p))
+    }
+
+    /// Where a guard at `p` facing `d` stops. `p` must be an open cell inside the maze.
+    pub fn stop(&self, p: Point, d: Direction4) -> Point {
+        let stop = self.stops[self.index(p, d)];
+
+        match d {
+            Direction4::Up | Direction4::Down => Point::new(p.x, stop),
+            Direction4::Left | Direction4::Right => Point::new(stop, p.y),
+        }
+    }
+
+    /// Block `obstacle`, rewriting the stops of the open cells that lead straight into it.
+    pub fn add_obstacle(&mut self, obstacle: Point) -> JumpTablePatch {
+        let mut saved = vec![];
+
+        for d in Direction4::ALL {
+            // Cells approaching the obstacle heading in direction `d` lie behind it
+            let behind = -d.offset();
+            let stop = match d {
+                Direction4::Up => obstacle.y + 1,
+                Direction4::Down => obstacle.y - 1,
+                Direction4::Left => obstacle.x + 1,
+                Direction4::Right => obstacle.x - 1,
+            };
+
+            let mut p = obstacle + behind;
+
+            while self.in_bounds(p) && !self.is_blocked(p) {
+                let index = self.index(p, d);
+
+                saved.push((index, self.stops[index]));
+                self.stops[index] = stop;
+
+                p += behind;
+            }
+        }
+
+        let cell = self.cell_index(obstacle);
+        let obstacle = self.blocked.insert(cell).then_some(cell);
+
+        JumpTablePatch { obstacle, saved }
+    }
+
+    /// Undo an [`add_obstacle`](JumpTable::add_obstacle).
+    pub fn restore(&mut self, patch: JumpTablePatch) {
+        for (index, value) in patch.saved {
+            self.stops[index] = value;
+        }
+
+        if let Some(cell) = patch.obstacle {
+            self.blocked.remove(cell);
+        }
+    }
+}
+
+/// [`walk_guard`] on a jump table, jumping from turn to turn. `turns` is scratch space of `4 * width * height` bits.
+pub fn walk_guard_jumping(table: &JumpTable, guard: GuardState, turns: &mut BitSet) -> WalkOutcome {
+    turns.clear();
+
+    let mut state = guard;
+
+    loop {
+        let stop = table.stop(state.position, state.facing);
+
+        if !table.in_bounds(stop) {
+            return WalkOutcome::Exits;
+        }
+
+        state = GuardState {
+            position: stop,
+            facing: state.facing.turn_right(),
+        };
+
+        if !turns.insert(table.index(state.position, state.facing)) {
+            return WalkOutcome::Loops;
+        }
+    }
+}
+
+/// The distinct cells the guard enters before leaving the maze, starting cell included.
+pub fn guard_path(m: &Maze, guard: GuardState) -> BTreeSet<Point> {
+    let table = JumpTable::new(m);
+    let mut turns = BitSet::new(m.width() * m.height() * 4);
+    let mut path = BitSet::new(m.width() * m.height());
+    let mut state = guard;
+
+    loop {
+        let stop = table.stop(state.position, state.facing);
+        let step = state.facing.offset();
+
+        let mut p = state.position;
+        while p != stop && table.in_bounds(p) {
+            path.insert(table.cell_index(p));
+            p += step;
+        }
+
+        if !table.in_bounds(stop) {
+            break;
+        }
+
+        path.insert(table.cell_index(stop));
+
+        state = GuardState {
+            position: stop,
+            facing: state.facing.turn_right(),
+        };
+
+        // A guard that loops without an added obstacle never leaves
+        if !turns.insert(table.index(state.position, state.facing)) {
+            break;
+        }
+    }
+
+    path.iter().map(|cell| table.cell_point(cell)).collect()
+}
+
+/// Every position where a single extra barrier traps the guard in a loop.
+///
+/// Only cells on the original path can change the walk, and the guard's starting cell is excluded.
+pub fn loop_obstructions(m: &Maze, guard: GuardState) -> BTreeSet<Point> {
+    let mut table = JumpTable::new(m);
+    let mut turns = BitSet::new(m.width() * m.height() * 4);
+
+    guard_path(m, guard).into_iter()
+        .filter(|p| *p != guard.position)
+        .filter(|p| {
+            let patch = table.add_obstacle(*p);
+            let outcome = walk_guard_jumping(&table, guard, &mut turns);
+
+            table.restore(patch);
+
+            outcome == WalkOutcome::Loops
+        })
+        .collect()
+}
+
+pub struct Advent6 {
+    maze: Maze,
+    guard: GuardState,
+}
+
+impl Solution for Advent6 {
+    fn parse(input: &str) -> Result<Self, InputError> {
+        let (maze, guard) = parse_maze(input, &Legend::default())?;
+
+        Ok(Self { maze, guard })
+    }
+
+    fn part1(&self) -> Answer {
+        guard_path(&self.maze, self.guard).len().into()
+    }
+
+    fn part2(&self) -> Answer {
+        loop_obstructions(&self.maze, self.guard).len().into()
+    }
+}
+
 #[test]
 fn input_move_guard_to_exit() {
-    let mut maze = read_maze_lines("advent6a-input.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-input.txt").unwrap()).unwrap();
 
-    let guard_state = find_guard(&maze);
+    let guard_state = find_guard(&maze).unwrap();
 
-    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&mut maze, guard_state);
+    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&maze, guard_state);
 
     let terminal_state = guard_states.last().unwrap();
 
@@ -296,104 +766,289 @@ fn input_move_guard_to_exit() {
     assert_eq!(5329, moves);
     assert_eq!(130, terminal_state.position.y);
     assert_eq!(9, terminal_state.position.x);
-    assert_eq!(Direction::Down, terminal_state.facing);
+    assert_eq!(Direction4::Down, terminal_state.facing);
 }
 
 #[test]
 fn test_move_guard_to_exit() {
-    let mut maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
 
-    let guard_state = find_guard(&maze);
+    let guard_state = find_guard(&maze).unwrap();
 
-    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&mut maze, guard_state);
+    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&maze, guard_state);
 
     let terminal_state = guard_states.last().unwrap();
 
     assert_eq!(41, moves);
     assert_eq!(10, terminal_state.position.y);
     assert_eq!(7, terminal_state.position.x);
-    assert_eq!(Direction::Down, terminal_state.facing);
+    assert_eq!(Direction4::Down, terminal_state.facing);
 
-    assert!(barrier_locations.contains(&Position::new(3, 6)));
-    assert!(barrier_locations.contains(&Position::new(6, 7)));
-    assert!(barrier_locations.contains(&Position::new(7, 7)));
-    assert!(barrier_locations.contains(&Position::new(1, 8))); // Failed
-    assert!(barrier_locations.contains(&Position::new(3, 8))); // Failed
-    assert!(barrier_locations.contains(&Position::new(7, 9)));
+    assert!(barrier_locations.contains(&Point::new(3, 6)));
+    assert!(barrier_locations.contains(&Point::new(6, 7)));
+    assert!(barrier_locations.contains(&Point::new(7, 7)));
+    assert!(barrier_locations.contains(&Point::new(1, 8))); // Failed
+    assert!(barrier_locations.contains(&Point::new(3, 8))); // Failed
+    assert!(barrier_locations.contains(&Point::new(7, 9)));
 
     assert_eq!(6, barrier_locations.len());
 }
 
 #[test]
 fn test_move_guard_to_stop_exit() {
-    let mut maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
 
     let guard_state = GuardState {
-        position: Position::new_i32(3, 2),
-        facing: Direction::Up,
+        position: Point::new(3, 2),
+        facing: Direction4::Up,
     };
 
-    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&mut maze, guard_state);
+    let (guard_states, moves, _) = move_guard_until_exit(&maze, guard_state);
 
     let terminal_state = guard_states.last().unwrap();
 
     assert_eq!(3, moves);
     assert_eq!(-1, terminal_state.position.y);
     assert_eq!(3, terminal_state.position.x);
-    assert_eq!(Direction::Up, terminal_state.facing);
+    assert_eq!(Direction4::Up, terminal_state.facing);
 }
 
 #[test]
 fn test_move_guard_to_stop() {
-    let mut maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
 
-    let guard_state = find_guard(&maze);
+    let guard_state = find_guard(&maze).unwrap();
 
-    let (guard_states, moves, barrier_locations) = move_guard_until_exit(&mut maze, guard_state);
+    let (guard_states, moves) = move_guard_until_stop(&maze, &mut vec![guard_state]);
 
     let terminal_state = guard_states.last().unwrap();
 
     assert_eq!(5, moves);
     assert_eq!(1, terminal_state.position.y);
     assert_eq!(4, terminal_state.position.x);
-    assert_eq!(Direction::Up, terminal_state.facing);
+    assert_eq!(Direction4::Up, terminal_state.facing);
 }
 
 #[test]
 fn test_move_guard_single() {
-    let mut maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
 
-    let guard_state = find_guard(&maze);
+    let guard_state = find_guard(&maze).unwrap();
 
-    let guard_state = move_guard_single(&mut maze, &guard_state);
+    let guard_state = move_guard_single(&maze, &guard_state);
 
-    if let (Some(guard_state)) = guard_state {
-        assert_eq!(5, guard_state.position.y);
-        assert_eq!(4, guard_state.position.x);
-        assert_eq!(Direction::Up, guard_state.facing);
-    } else {
-        assert!(false);
-    }
+    let guard_state = guard_state.expect("Guard should be able to move");
+
+    assert_eq!(5, guard_state.position.y);
+    assert_eq!(4, guard_state.position.x);
+    assert_eq!(Direction4::Up, guard_state.facing);
 }
 
 #[test]
 fn test_find_guard() {
-    let maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
 
-    let guard_state = find_guard(&maze);
+    let guard_state = find_guard(&maze).unwrap();
 
     assert_eq!(6, guard_state.position.y);
     assert_eq!(4, guard_state.position.x);
-    assert_eq!(Direction::Up, guard_state.facing);
+    assert_eq!(Direction4::Up, guard_state.facing);
 }
 
 #[test]
 fn read_test_maze_lines() {
-    let maze = read_maze_lines("advent6a-test.txt");
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    assert_eq!(10, maze.height());
+    assert_eq!(10, maze.width());
+    assert_eq!(Some(&MazeCell::Guard(Direction4::Up)), maze.get(Point::new(4, 6)));
+    assert_eq!(Some(&MazeCell::Open), maze.get(Point::new(2, 2)));
+    assert_eq!(Some(&MazeCell::Blocked), maze.get(Point::new(4, 0)));
+}
+
+#[test]
+fn test_solution() {
+    let solution = Advent6::from_file("inputs/advent6a-test.txt").unwrap();
+
+    assert_eq!(Answer::Unsigned(41), solution.part1());
+    assert_eq!(Answer::Unsigned(6), solution.part2());
+}
+
+#[test]
+fn test_loop_obstructions() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let guard_state = find_guard(&maze).unwrap();
+
+    let expected: BTreeSet<Point> = [(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)]
+        .into_iter()
+        .map(|(x, y)| Point::new(x, y))
+        .collect();
+
+    assert_eq!(expected, loop_obstructions(&maze, guard_state));
+    assert_eq!(41, guard_path(&maze, guard_state).len());
+}
+
+#[test]
+fn walk_guard_detects_loops() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let guard_state = find_guard(&maze).unwrap();
+
+    assert_eq!(WalkOutcome::Exits, walk_guard(&maze, guard_state, None));
+    assert_eq!(WalkOutcome::Loops, walk_guard(&maze, guard_state, Some(Point::new(3, 6))));
+    assert_eq!(WalkOutcome::Exits, walk_guard(&maze, guard_state, Some(Point::new(0, 0))));
+}
+
+#[test]
+fn input_loop_obstructions_cross_check() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-input.txt").unwrap()).unwrap();
+
+    let guard_state = find_guard(&maze).unwrap();
+
+    let exact = loop_obstructions(&maze, guard_state);
+    let (_, _, heuristic) = move_guard_until_exit(&maze, guard_state);
+    let heuristic: BTreeSet<Point> = heuristic.into_iter().collect();
+
+    // The heuristic misses most loops and reports a handful of barriers that do not cause one
+    assert_eq!(2162, exact.len());
+    assert_eq!(1660, exact.difference(&heuristic).count());
+    assert_eq!(5, heuristic.difference(&exact).count());
+}
+
+#[test]
+fn jump_table_stops() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let table = JumpTable::new(&maze);
+
+    assert_eq!(Point::new(4, 1), table.stop(Point::new(4, 6), Direction4::Up));
+    assert_eq!(Point::new(8, 1), table.stop(Point::new(4, 1), Direction4::Right));
+    assert_eq!(Point::new(4, 10), table.stop(Point::new(4, 6), Direction4::Down));
+    assert_eq!(Point::new(2, 6), table.stop(Point::new(4, 6), Direction4::Left));
+    assert_eq!(Point::new(-1, 5), table.stop(Point::new(4, 5), Direction4::Left));
+}
+
+#[test]
+fn jump_table_patch_and_restore() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let mut table = JumpTable::new(&maze);
+    let original = table.stops.clone();
+
+    let patch = table.add_obstacle(Point::new(4, 3));
+
+    assert!(table.is_blocked(Point::new(4, 3)));
+    assert_eq!(Point::new(4, 4), table.stop(Point::new(4, 6), Direction4::Up));
+    assert_eq!(Point::new(4, 2), table.stop(Point::new(4, 1), Direction4::Down));
+    assert_eq!(Point::new(3, 3), table.stop(Point::new(3, 3), Direction4::Right));
+    assert_eq!(Point::new(1, 3), table.stop(Point::new(0, 3), Direction4::Right));
+    assert_eq!(Point::new(5, 3), table.stop(Point::new(9, 3), Direction4::Left));
+
+    table.restore(patch);
+
+    assert!(!table.is_blocked(Point::new(4, 3)));
+    assert_eq!(original, table.stops);
+}
+
+#[test]
+fn jumping_walk_matches_stepping_walk() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let guard_state = find_guard(&maze).unwrap();
+    let mut table = JumpTable::new(&maze);
+    let mut turns = BitSet::new(maze.width() * maze.height() * 4);
+
+    for p in maze.positions().filter(|p| *p != guard_state.position) {
+        let patch = table.add_obstacle(p);
+
+        assert_eq!(walk_guard(&maze, guard_state, Some(p)), walk_guard_jumping(&table, guard_state, &mut turns), "obstacle at {p:?}");
+
+        table.restore(patch);
+    }
+}
+
+#[test]
+fn stepper_yields_turns_and_outcome() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let guard_state = find_guard(&maze).unwrap();
+    let mut stepper = GuardStepper::new(&maze, guard_state, None);
+
+    let first: Vec<GuardState> = stepper.by_ref().take(7).collect();
+
+    assert_eq!(guard_state, first[0]);
+    assert_eq!(GuardState { position: Point::new(4, 1), facing: Direction4::Up }, first[5]);
+    assert_eq!(GuardState { position: Point::new(4, 1), facing: Direction4::Right }, first[6]);
+    assert_eq!(None, stepper.outcome());
+
+    stepper.by_ref().for_each(drop);
+
+    assert_eq!(Some(WalkOutcome::Exits), stepper.outcome());
+
+    let mut looping = GuardStepper::new(&maze, guard_state, Some(Point::new(3, 6)));
+    looping.by_ref().for_each(drop);
+
+    assert_eq!(Some(WalkOutcome::Loops), looping.outcome());
+}
+
+#[test]
+fn maze_errors() {
+    let error = |contents: &str| parse_maze(contents, &Legend::default()).err().unwrap().to_string();
+
+    assert_eq!("<input>:2:3: row is 2 cells wide, expected 3", error("...\n.^\n..."));
+    assert_eq!("<input>:1:2: unknown cell character `X`", error(".X.\n.^."));
+    assert_eq!("<input>: no guard found in maze", error("...\n.#."));
+    assert_eq!("<input>: maze is empty", error(""));
+    assert_eq!("<input>:3:1: second guard found; the first is at line 1, column 2", error(".>.\n...\n<.."));
+}
+
+#[test]
+fn maze_legend() {
+    let legend = Legend::new().with('X', MazeCell::Blocked).with('o', MazeCell::Open);
+
+    let (maze, guard) = parse_maze(".X.\noo^", &legend).unwrap();
+
+    assert_eq!(Some(&MazeCell::Blocked), maze.get(Point::new(1, 0)));
+    assert_eq!(Some(&MazeCell::Open), maze.get(Point::new(0, 1)));
+    assert_eq!(GuardState { position: Point::new(2, 1), facing: Direction4::Up }, guard);
+}
+
+#[test]
+fn turn_rules() {
+    assert_eq!(Direction4::Right, TurnRule::Right.turn(Direction4::Up));
+    assert_eq!(Direction4::Left, TurnRule::Left.turn(Direction4::Up));
+    assert_eq!(Direction4::Down, TurnRule::Reverse.turn(Direction4::Up));
+}
+
+#[test]
+fn simulate_single_guard_matches_part1() {
+    let maze = read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+
+    let report = simulate_guards(&maze, &find_guards(&maze), TurnRule::Right);
+
+    assert_eq!(1, report.guards.len());
+    assert_eq!(41, report.guards[0].visited);
+    assert_eq!(WalkOutcome::Exits, report.guards[0].outcome);
+    assert!(report.collisions.is_empty());
+}
+
+#[test]
+fn simulate_guards_with_rules_and_collisions() {
+    let maze = read_maze_lines(".#...\n.....\n>...<\n.....").unwrap();
+    let guards = find_guards(&maze);
+
+    // Walking straight towards each other, the guards meet in the middle before both leaving
+    let report = simulate_guards(&maze, &guards, TurnRule::Right);
+
+    assert_eq!(vec![WalkOutcome::Exits, WalkOutcome::Exits], report.guards.iter().map(|g| g.outcome).collect::<Vec<_>>());
+    assert_eq!(vec![5, 5], report.guards.iter().map(|g| g.visited).collect::<Vec<_>>());
+    assert_eq!(Collision { tick: 2, position: Point::new(2, 2), guards: vec![0, 1] }, report.collisions[0]);
+
+    // Reversing at a wall sends a guard back and forth for ever
+    let maze = read_maze_lines("#>.#").unwrap();
+    let report = simulate_guards(&maze, &find_guards(&maze), TurnRule::Reverse);
 
-    assert_eq!(10, maze.len());
-    assert_eq!(10, maze[0].len());
-    assert_eq!(MazeCell::Guard(Direction::Up), maze[6][4]);
-    assert_eq!(MazeCell::Open, maze[2][2]);
-    assert_eq!(MazeCell::Blocked, maze[0][4]);
+    assert_eq!(WalkOutcome::Loops, report.guards[0].outcome);
+    assert_eq!(2, report.guards[0].visited);
 }
@@ -0,0 +1,215 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::geometry::{Direction4, Point};
+use crate::grid::Grid;
+
+use super::{GuardState, Maze, MazeCell};
+
+/// Pixel size of one maze cell in SVG output.
+const SVG_CELL: i32 = 10;
+
+/// A set of obstruction positions drawn in one colour in SVG output, with a legend entry.
+pub struct Overlay<'a> {
+    pub label: &'a str,
+    pub colour: &'a str,
+    pub points: &'a BTreeSet<Point>,
+}
+
+fn guard_char(d: Direction4) -> char {
+    match d {
+        Direction4::Up => '^',
+        Direction4::Right => '>',
+        Direction4::Down => 'v',
+        Direction4::Left => '<',
+    }
+}
+
+/// Draw the maze with the guard's path overlaid: `|` and `-` for vertical and horizontal movement, `+` where the
+/// guard turned or crossed its own path, and `O` for each obstruction.
+pub fn render_text(m: &Maze, path: &[GuardState], obstructions: &BTreeSet<Point>) -> String {
+    draw(m, path, obstructions, None)
+}
+
+/// One frame of a replay: the path walked so far, with the guard drawn at its latest state instead of its start.
+pub fn render_frame(m: &Maze, path: &[GuardState], obstructions: &BTreeSet<Point>) -> String {
+    draw(m, path, obstructions, path.last())
+}
+
+fn draw(m: &Maze, path: &[GuardState], obstructions: &BTreeSet<Point>, guard: Option<&GuardState>) -> String {
+    let mut travel: Grid<(bool, bool)> = Grid::from_rows(vec![vec![(false, false); m.width()]; m.height()])
+        .unwrap_or_else(|_| unreachable!("rows are all the same width"));
+
+    for state in path {
+        if let Some((vertical, horizontal)) = travel.get_mut(state.position) {
+            match state.facing {
+                Direction4::Up | Direction4::Down => *vertical = true,
+                Direction4::Left | Direction4::Right => *horizontal = true,
+            }
+        }
+    }
+
+    let mut out = String::new();
+
+    for (p, cell) in m.iter() {
+        let c = match (cell, travel.get(p)) {
+            _ if obstructions.contains(&p) => 'O',
+            (MazeCell::Blocked, _) => '#',
+            (MazeCell::Guard(d), _) if guard.is_none() => guard_char(*d),
+            (_, Some((true, true))) => '+',
+            (_, Some((true, false))) => '|',
+            (_, Some((false, true))) => '-',
+            _ => '.',
+        };
+
+        match guard {
+            Some(g) if g.position == p => out.push(guard_char(g.facing)),
+            _ => out.push(c),
+        }
+
+        if p.x as usize == m.width() - 1 {
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+fn svg_centre(p: Point) -> (i32, i32) {
+    (p.x * SVG_CELL + SVG_CELL / 2, p.y * SVG_CELL + SVG_CELL / 2)
+}
+
+/// Draw the same scene as [`render_text`] as an SVG document, with one colour per obstruction overlay.
+pub fn render_svg(m: &Maze, path: &[GuardState], overlays: &[Overlay]) -> String {
+    let width = m.width() as i32 * SVG_CELL;
+    let height = m.height() as i32 * SVG_CELL;
+    let legend_height = overlays.len() as i32 * 2 * SVG_CELL;
+
+    let mut out = String::new();
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{}" viewBox="0 0 {width} {}">"#,
+        height + legend_height,
+        height + legend_height
+    ).unwrap();
+    writeln!(out, r#"<rect width="{width}" height="{height}" fill="white" stroke="grey"/>"#).unwrap();
+
+    for (p, cell) in m.iter() {
+        if *cell == MazeCell::Blocked {
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{SVG_CELL}" height="{SVG_CELL}" fill="black"/>"#,
+                p.x * SVG_CELL,
+                p.y * SVG_CELL
+            ).unwrap();
+        }
+    }
+
+    if !path.is_empty() {
+        let points: Vec<String> = path.iter()
+            .filter(|state| m.in_bounds(state.position))
+            .map(|state| {
+                let (x, y) = svg_centre(state.position);
+                format!("{x},{y}")
+            })
+            .collect();
+
+        writeln!(out, r#"<polyline points="{}" fill="none" stroke="steelblue" stroke-width="2"/>"#, points.join(" ")).unwrap();
+
+        let (x, y) = svg_centre(path[0].position);
+        writeln!(out, r#"<circle cx="{x}" cy="{y}" r="{}" fill="green"/>"#, SVG_CELL / 2).unwrap();
+    }
+
+    for (idx, overlay) in overlays.iter().enumerate() {
+        for p in overlay.points {
+            let (x, y) = svg_centre(*p);
+            writeln!(
+                out,
+                r#"<circle cx="{x}" cy="{y}" r="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                SVG_CELL / 2 - 1,
+                overlay.colour
+            ).unwrap();
+        }
+
+        let legend_y = height + (idx as i32 * 2 + 1) * SVG_CELL;
+        writeln!(
+            out,
+            r#"<text x="0" y="{}" font-size="{}" fill="{}">{} ({})</text>"#,
+            legend_y + SVG_CELL / 2,
+            SVG_CELL,
+            overlay.colour,
+            overlay.label,
+            overlay.points.len()
+        ).unwrap();
+    }
+
+    out.push_str("</svg>\n");
+
+    out
+}
+
+#[cfg(test)]
+fn test_scene() -> (Maze, Vec<GuardState>) {
+    let maze = super::read_maze_lines(&crate::input::read_input("inputs/advent6a-test.txt").unwrap()).unwrap();
+    let guard = super::find_guard(&maze).unwrap();
+    let (trail, _) = super::guard_trail(&maze, guard, None);
+
+    (maze, trail)
+}
+
+#[test]
+fn render_text_path() {
+    let (maze, trail) = test_scene();
+
+    let expected = "\
+....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+.+----++#.
+#+----+|..
+......#O..
+";
+
+    assert_eq!(expected, render_text(&maze, &trail, &[Point::new(7, 9)].into()));
+}
+
+#[test]
+fn render_svg_scene() {
+    let (maze, trail) = test_scene();
+    let exact: BTreeSet<Point> = [Point::new(3, 6), Point::new(7, 9)].into();
+
+    let svg = render_svg(&maze, &trail, &[Overlay { label: "exact", colour: "red", points: &exact }]);
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(8, svg.matches(r#"fill="black""#).count());
+    assert_eq!(2, svg.matches(r#"stroke="red""#).count());
+    assert!(svg.contains(">exact (2)</text>"));
+}
+
+#[test]
+fn render_replay_frame() {
+    let (maze, trail) = test_scene();
+
+    let frame = render_frame(&maze, &trail[..7], &BTreeSet::new());
+
+    let expected = "\
+....#.....
+....>....#
+....|.....
+..#.|.....
+....|..#..
+....|.....
+.#..|.....
+........#.
+#.........
+......#...
+";
+
+    assert_eq!(expected, frame);
+}
@@ -0,0 +1,193 @@
+use std::fmt::Write;
+
+use crate::{
+    input::{self, InputError},
+    runner::{InputSource, PARTS, PUZZLES},
+};
+
+/// Name of the registry file inside the inputs directory.
+pub const ANSWERS_FILE: &str = "answers.txt";
+
+/// A known answer for one day, part and input, as recorded in the registry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpectedAnswer {
+    pub day: u32,
+    pub part: char,
+    /// Input files inside the inputs directory, joined with `+` when a day reads more than one.
+    pub input: String,
+    pub answer: String,
+}
+
+/// Read the registry: one `<day> <part> <input> <answer>` entry per line, `#` starts a comment.
+pub fn read_answers(contents: &str) -> Result<Vec<ExpectedAnswer>, InputError> {
+    input::lines(contents)
+        .filter(|line| {
+            let text = line.text.trim();
+            !text.is_empty() && !text.starts_with('#')
+        })
+        .map(|line| {
+            let mut fields = line.text.split_whitespace();
+
+            let day = line.expect(&mut fields, "day")?;
+            let part = line.expect(&mut fields, "part")?;
+            let input = line.expect(&mut fields, "input file")?;
+            let answer = line.expect(&mut fields, "answer")?;
+
+            let part = match part {
+                "a" => 'a',
+                "b" => 'b',
+                _ => return Err(line.error(part, format!("unknown part `{part}`, expected a or b"))),
+            };
+
+            if let Some(extra) = fields.next() {
+                return Err(line.error(extra, "unexpected text after answer"));
+            }
+
+            Ok(ExpectedAnswer {
+                day: line.parse(day)?,
+                part,
+                input: input.to_string(),
+                answer: answer.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub enum CheckStatus {
+    Pass,
+    Fail { actual: String },
+    /// No answer is recorded for this day, part and input.
+    Missing { actual: String },
+    Error(String),
+}
+
+#[derive(Debug)]
+pub struct CheckResult {
+    pub day: u32,
+    pub part: char,
+    pub input: String,
+    pub expected: Option<String>,
+    pub status: CheckStatus,
+}
+
+/// Run every solver against each registered input, plus the default input of every puzzle.
+pub fn check_all(inputs_dir: &str, answers: &[ExpectedAnswer]) -> Vec<CheckResult> {
+    let mut results = vec![];
+
+    for puzzle in PUZZLES.iter() {
+        let mut inputs: Vec<String> = vec![puzzle.default_inputs.join("+")];
+
+        for answer in answers.iter().filter(|a| a.day == puzzle.day) {
+            if !inputs.contains(&answer.input) {
+                inputs.push(answer.input.clone());
+            }
+        }
+
+        for input in inputs {
+            let files: Vec<&str> = input.split('+').collect();
+
+            let actual = InputSource::from_inputs_dir(inputs_dir, &files)
+                .and_then(|source| (puzzle.solve)(&source, &PARTS));
+
+            for part in PARTS {
+                let expected = answers.iter()
+                    .find(|a| a.day == puzzle.day && a.part == part && a.input == input)
+                    .map(|a| a.answer.clone());
+
+                let status = match (&actual, &expected) {
+                    (Err(err), _) => CheckStatus::Error(err.to_string()),
+                    (Ok(actual), expected) => {
+                        let actual = actual.parts.iter()
+                            .find(|r| r.part == part)
+                            .map(|r| r.answer.to_string())
+                            .unwrap_or_default();
+
+                        match expected {
+                            None => CheckStatus::Missing { actual },
+                            Some(expected) if *expected == actual => CheckStatus::Pass,
+                            Some(_) => CheckStatus::Fail { actual },
+                        }
+                    }
+                };
+
+                results.push(CheckResult {
+                    day: puzzle.day,
+                    part,
+                    input: input.clone(),
+                    expected,
+                    status,
+                });
+            }
+        }
+    }
+
+    results
+}
+
+pub fn format_table(results: &[CheckResult]) -> String {
+    let input_width = results.iter()
+        .map(|r| r.input.len())
+        .chain(std::iter::once("input".len()))
+        .max()
+        .unwrap_or_default();
+
+    let mut table = String::new();
+
+    writeln!(table, "day part {:input_width$} status   expected         actual", "input").unwrap();
+
+    for result in results {
+        let expected = result.expected.as_deref().unwrap_or("-");
+
+        let (status, actual) = match &result.status {
+            CheckStatus::Pass => ("pass", expected.to_string()),
+            CheckStatus::Fail { actual } => ("FAIL", actual.clone()),
+            CheckStatus::Missing { actual } => ("missing", actual.clone()),
+            CheckStatus::Error(err) => ("ERROR", err.clone()),
+        };
+
+        writeln!(table, "{:>3} {:>4} {:input_width$} {:8} {:16} {}", result.day, result.part, result.input, status, expected, actual).unwrap();
+    }
+
+    let count = |f: fn(&CheckStatus) -> bool| results.iter().filter(|r| f(&r.status)).count();
+
+    write!(
+        table,
+        "{} passed, {} failed, {} missing, {} errors",
+        count(|s| matches!(s, CheckStatus::Pass)),
+        count(|s| matches!(s, CheckStatus::Fail { .. })),
+        count(|s| matches!(s, CheckStatus::Missing { .. })),
+        count(|s| matches!(s, CheckStatus::Error(_))),
+    )
+    .unwrap();
+
+    table
+}
+
+#[test]
+fn read_answers_skips_comments() {
+    let answers = read_answers("# day part input answer\n\n1 a advent1-input.txt 11\n5 b x.txt+y.txt 123\n").unwrap();
+
+    assert_eq!(2, answers.len());
+    assert_eq!(ExpectedAnswer { day: 5, part: 'b', input: "x.txt+y.txt".to_string(), answer: "123".to_string() }, answers[1]);
+}
+
+#[test]
+fn read_answers_rejects_unknown_part() {
+    let err = read_answers("1 c advent1-input.txt 11").unwrap_err();
+
+    assert_eq!("<input>:1:3: unknown part `c`, expected a or b", err.to_string());
+}
+
+#[test]
+fn registered_answers_pass() {
+    let answers = read_answers(&input::read_input("inputs/answers.txt").unwrap()).unwrap();
+
+    let results = check_all("inputs", &answers);
+
+    assert!(
+        results.iter().all(|r| matches!(r.status, CheckStatus::Pass)),
+        "{}",
+        format_table(&results)
+    );
+}
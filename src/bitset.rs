@@ -0,0 +1,82 @@
+/// A fixed-capacity set of indexes `0..capacity`, stored one bit per index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitSet {
+    capacity: usize,
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(capacity: usize) -> BitSet {
+        BitSet { capacity, words: vec![0; capacity.div_ceil(64)] }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        index < self.capacity && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Adds `index`, returning whether it was newly added. Panics if `index` is out of range.
+    pub fn insert(&mut self, index: usize) -> bool {
+        assert!(index < self.capacity, "index {index} out of range for bit set of {}", self.capacity);
+
+        let word = &mut self.words[index / 64];
+        let bit = 1 << (index % 64);
+        let added = *word & bit == 0;
+
+        *word |= bit;
+        added
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.capacity {
+            self.words[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The indexes in the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.capacity).filter(|i| self.contains(*i))
+    }
+}
+
+#[test]
+fn insert_and_contains() {
+    let mut set = BitSet::new(130);
+
+    assert!(set.insert(0));
+    assert!(set.insert(64));
+    assert!(set.insert(129));
+    assert!(!set.insert(64));
+
+    assert!(set.contains(129));
+    assert!(!set.contains(1));
+    assert!(!set.contains(500));
+    assert_eq!(3, set.count());
+    assert_eq!(vec![0, 64, 129], set.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn remove_and_clear() {
+    let mut set = BitSet::new(10);
+
+    set.insert(3);
+    set.insert(7);
+    set.remove(3);
+
+    assert_eq!(vec![7], set.iter().collect::<Vec<_>>());
+
+    set.clear();
+
+    assert_eq!(0, set.count());
+}
@@ -0,0 +1,292 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A location on a 2D plane with `x` to the right and `y` down, matching the row/column layout of puzzle input.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A displacement between two points.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Default)]
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+
+    pub fn manhattan(&self, other: Point) -> i32 {
+        (*self - other).manhattan_length()
+    }
+
+    pub fn chebyshev(&self, other: Point) -> i32 {
+        (*self - other).chebyshev_length()
+    }
+}
+
+impl Vector {
+    pub const ZERO: Vector = Vector::new(0, 0);
+
+    pub const fn new(x: i32, y: i32) -> Vector {
+        Vector { x, y }
+    }
+
+    pub fn manhattan_length(&self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+
+    pub fn chebyshev_length(&self) -> i32 {
+        self.x.abs().max(self.y.abs())
+    }
+
+    /// Quarter turn clockwise on screen (with `y` pointing down).
+    pub fn rotate_right(&self) -> Vector {
+        Vector::new(-self.y, self.x)
+    }
+
+    /// Quarter turn anticlockwise on screen (with `y` pointing down).
+    pub fn rotate_left(&self) -> Vector {
+        Vector::new(self.y, -self.x)
+    }
+}
+
+impl Add<Vector> for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Vector) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign<Vector> for Point {
+    fn add_assign(&mut self, rhs: Vector) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Vector> for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Vector) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign<Vector> for Point {
+    fn sub_assign(&mut self, rhs: Vector) {
+        *self = *self - rhs;
+    }
+}
+
+impl Sub<Point> for Point {
+    type Output = Vector;
+
+    fn sub(self, rhs: Point) -> Vector {
+        Vector::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, rhs: Vector) -> Vector {
+        Vector::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, rhs: Vector) -> Vector {
+        Vector::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+
+    fn neg(self) -> Vector {
+        Vector::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i32> for Vector {
+    type Output = Vector;
+
+    fn mul(self, rhs: i32) -> Vector {
+        Vector::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+/// The four orthogonal directions, listed clockwise from up.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Direction4 {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction4 {
+    pub const ALL: [Direction4; 4] = [Direction4::Up, Direction4::Right, Direction4::Down, Direction4::Left];
+
+    pub fn offset(&self) -> Vector {
+        match self {
+            Direction4::Up => Vector::new(0, -1),
+            Direction4::Right => Vector::new(1, 0),
+            Direction4::Down => Vector::new(0, 1),
+            Direction4::Left => Vector::new(-1, 0),
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction4 {
+        Direction4::ALL[(*self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(&self) -> Direction4 {
+        Direction4::ALL[(*self as usize + 3) % 4]
+    }
+
+    pub fn reverse(&self) -> Direction4 {
+        Direction4::ALL[(*self as usize + 2) % 4]
+    }
+}
+
+/// The eight compass directions, listed clockwise from up.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Direction8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Direction8 {
+    pub const ALL: [Direction8; 8] = [
+        Direction8::Up,
+        Direction8::UpRight,
+        Direction8::Right,
+        Direction8::DownRight,
+        Direction8::Down,
+        Direction8::DownLeft,
+        Direction8::Left,
+        Direction8::UpLeft,
+    ];
+
+    pub const DIAGONALS: [Direction8; 4] = [Direction8::UpRight, Direction8::DownRight, Direction8::DownLeft, Direction8::UpLeft];
+
+    pub fn offset(&self) -> Vector {
+        match self {
+            Direction8::Up => Vector::new(0, -1),
+            Direction8::UpRight => Vector::new(1, -1),
+            Direction8::Right => Vector::new(1, 0),
+            Direction8::DownRight => Vector::new(1, 1),
+            Direction8::Down => Vector::new(0, 1),
+            Direction8::DownLeft => Vector::new(-1, 1),
+            Direction8::Left => Vector::new(-1, 0),
+            Direction8::UpLeft => Vector::new(-1, -1),
+        }
+    }
+
+    /// An eighth of a turn clockwise.
+    pub fn turn_right(&self) -> Direction8 {
+        Direction8::ALL[(*self as usize + 1) % 8]
+    }
+
+    /// An eighth of a turn anticlockwise.
+    pub fn turn_left(&self) -> Direction8 {
+        Direction8::ALL[(*self as usize + 7) % 8]
+    }
+
+    pub fn reverse(&self) -> Direction8 {
+        Direction8::ALL[(*self as usize + 4) % 8]
+    }
+}
+
+impl From<Direction4> for Direction8 {
+    fn from(value: Direction4) -> Self {
+        match value {
+            Direction4::Up => Direction8::Up,
+            Direction4::Right => Direction8::Right,
+            Direction4::Down => Direction8::Down,
+            Direction4::Left => Direction8::Left,
+        }
+    }
+}
+
+#[test]
+fn point_vector_arithmetic() {
+    let p = Point::new(3, 4);
+    let v = Vector::new(1, -2);
+
+    assert_eq!(Point::new(4, 2), p + v);
+    assert_eq!(Point::new(2, 6), p - v);
+    assert_eq!(Vector::new(3, 4), p - Point::ORIGIN);
+    assert_eq!(Vector::new(-1, 2), -v);
+    assert_eq!(Vector::new(3, -6), v * 3);
+    assert_eq!(Vector::new(2, 0), v + Vector::new(1, 2));
+
+    let mut q = p;
+    q += v;
+    q -= v * 2;
+    assert_eq!(Point::new(2, 6), q);
+}
+
+#[test]
+fn distances() {
+    let a = Point::new(1, 1);
+    let b = Point::new(4, -1);
+
+    assert_eq!(5, a.manhattan(b));
+    assert_eq!(3, a.chebyshev(b));
+}
+
+#[test]
+fn direction4_rotations() {
+    assert_eq!(Direction4::Right, Direction4::Up.turn_right());
+    assert_eq!(Direction4::Left, Direction4::Up.turn_left());
+    assert_eq!(Direction4::Up, Direction4::Down.reverse());
+
+    for d in Direction4::ALL {
+        assert_eq!(d.turn_right().offset(), d.offset().rotate_right());
+        assert_eq!(d.turn_left().offset(), d.offset().rotate_left());
+        assert_eq!(d.reverse().offset(), -d.offset());
+    }
+}
+
+#[test]
+fn direction8_rotations() {
+    assert_eq!(Direction8::UpRight, Direction8::Up.turn_right());
+    assert_eq!(Direction8::UpLeft, Direction8::Up.turn_left());
+
+    for d in Direction8::ALL {
+        assert_eq!(d.reverse().offset(), -d.offset());
+        assert_eq!(d, d.turn_right().turn_left());
+    }
+
+    for d in Direction4::ALL {
+        assert_eq!(d.offset(), Direction8::from(d).offset());
+    }
+}
+
+#[test]
+fn points_order_and_hash() {
+    use std::collections::HashSet;
+
+    let mut points = vec![Point::new(2, 1), Point::new(1, 5), Point::new(1, 2)];
+    points.sort();
+    assert_eq!(vec![Point::new(1, 2), Point::new(1, 5), Point::new(2, 1)], points);
+
+    let set: HashSet<Point> = points.into_iter().chain([Point::new(1, 2)]).collect();
+    assert_eq!(3, set.len());
+}
@@ -0,0 +1,201 @@
+use crate::geometry::{Direction4, Direction8, Point, Vector};
+use crate::input::{self, InputError};
+
+/// A rectangular grid of cells addressed by signed points, `x` to the right and `y` down.
+///
+/// Lookups outside the grid return `None` rather than panicking, so callers can step off the edge freely.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from rows, which must all be the same length. On failure returns the index of the first ragged row.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, usize> {
+        let width = rows.first().map(|r| r.len()).unwrap_or(0);
+        let height = rows.len();
+
+        if let Some(ragged) = rows.iter().position(|r| r.len() != width) {
+            return Err(ragged);
+        }
+
+        Ok(Grid { width, height, cells: rows.into_iter().flatten().collect() })
+    }
+
+    /// Parse one row per line, mapping each character with `cell`; `None` rejects the character.
+    pub fn parse(contents: &str, mut cell: impl FnMut(char) -> Option<T>) -> Result<Grid<T>, InputError> {
+        let mut width: Option<usize> = None;
+        let mut rows = vec![];
+
+        for line in input::lines(contents) {
+            let row = line.text.chars()
+                .enumerate()
+                .map(|(idx, c)| cell(c).ok_or_else(|| line.error_at(idx + 1, format!("unknown cell character `{c}`"))))
+                .collect::<Result<Vec<T>, InputError>>()?;
+
+            match width {
+                None => width = Some(row.len()),
+                Some(width) if width != row.len() => {
+                    return Err(line.error_at(
+                        width.min(row.len()) + 1,
+                        format!("row is {} cells wide, expected {}", row.len(), width),
+                    ));
+                }
+                _ => {}
+            }
+
+            rows.push(row);
+        }
+
+        Ok(Grid::from_rows(rows).unwrap_or_else(|_| unreachable!("rows were checked while parsing")))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    fn offset(&self, p: Point) -> Option<usize> {
+        if self.in_bounds(p) {
+            Some(p.y as usize * self.width + p.x as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, p: Point) -> Option<&T> {
+        self.offset(p).map(|o| &self.cells[o])
+    }
+
+    pub fn get_mut(&mut self, p: Point) -> Option<&mut T> {
+        self.offset(p).map(|o| &mut self.cells[o])
+    }
+
+    /// Every point in the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+
+        (0..self.width * self.height).map(move |o| Point::new((o % width) as i32, (o / width) as i32))
+    }
+
+    /// Every cell with its point, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The neighbours at `offsets` from `p` that are inside the grid.
+    pub fn neighbours(&self, p: Point, offsets: impl IntoIterator<Item = Vector>) -> impl Iterator<Item = (Point, &T)> {
+        offsets.into_iter().filter_map(move |offset| {
+            let n = p + offset;
+
+            self.get(n).map(|cell| (n, cell))
+        })
+    }
+
+    pub fn neighbours4(&self, p: Point) -> impl Iterator<Item = (Point, &T)> {
+        self.neighbours(p, Direction4::ALL.map(|d| d.offset()))
+    }
+
+    pub fn neighbours8(&self, p: Point) -> impl Iterator<Item = (Point, &T)> {
+        self.neighbours(p, Direction8::ALL.map(|d| d.offset()))
+    }
+
+    /// Cells from `start` stepping by `step` until leaving the grid.
+    pub fn ray(&self, start: Point, step: Vector) -> impl Iterator<Item = &T> {
+        (0..)
+            .map(move |i| self.get(start + step * i))
+            .take_while(|cell| cell.is_some())
+            .flatten()
+    }
+
+    pub fn row(&self, y: i32) -> impl Iterator<Item = &T> {
+        self.ray(Point::new(0, y), Direction4::Right.offset())
+    }
+
+    pub fn column(&self, x: i32) -> impl Iterator<Item = &T> {
+        self.ray(Point::new(x, 0), Direction4::Down.offset())
+    }
+
+    /// Cells from `start` heading down and to the right.
+    pub fn diagonal(&self, start: Point) -> impl Iterator<Item = &T> {
+        self.ray(start, Direction8::DownRight.offset())
+    }
+
+    /// Cells from `start` heading down and to the left.
+    pub fn anti_diagonal(&self, start: Point) -> impl Iterator<Item = &T> {
+        self.ray(start, Direction8::DownLeft.offset())
+    }
+}
+
+#[cfg(test)]
+fn sample() -> Grid<char> {
+    Grid::parse("abc\ndef\nghi", Some).unwrap()
+}
+
+#[test]
+fn parse_and_get() {
+    let grid = sample();
+
+    assert_eq!(3, grid.width());
+    assert_eq!(3, grid.height());
+    assert_eq!(Some(&'f'), grid.get(Point::new(2, 1)));
+    assert_eq!(None, grid.get(Point::new(3, 1)));
+    assert_eq!(None, grid.get(Point::new(-1, 0)));
+}
+
+#[test]
+fn get_mut_updates_cell() {
+    let mut grid = sample();
+
+    *grid.get_mut(Point::new(1, 1)).unwrap() = 'X';
+
+    assert_eq!(Some(&'X'), grid.get(Point::new(1, 1)));
+    assert!(grid.get_mut(Point::new(1, 3)).is_none());
+}
+
+#[test]
+fn parse_rejects_ragged_rows() {
+    let err = Grid::parse("abc\nde\nfgh", Some).unwrap_err();
+
+    assert_eq!("<input>:2:3: row is 2 cells wide, expected 3", err.to_string());
+}
+
+#[test]
+fn parse_rejects_unknown_cells() {
+    let err = Grid::parse("..\n.x", |c| (c == '.').then_some(())).unwrap_err();
+
+    assert_eq!("<input>:2:2: unknown cell character `x`", err.to_string());
+}
+
+#[test]
+fn lines_through_grid() {
+    let grid = sample();
+
+    assert_eq!("def", grid.row(1).collect::<String>());
+    assert_eq!("beh", grid.column(1).collect::<String>());
+    assert_eq!("bf", grid.diagonal(Point::new(1, 0)).collect::<String>());
+    assert_eq!("ceg", grid.anti_diagonal(Point::new(2, 0)).collect::<String>());
+    assert_eq!("fed", grid.ray(Point::new(2, 1), Vector::new(-1, 0)).collect::<String>());
+}
+
+#[test]
+fn neighbours_stay_in_bounds() {
+    use itertools::Itertools;
+
+    let grid = sample();
+
+    assert_eq!(2, grid.neighbours4(Point::new(0, 0)).count());
+    assert_eq!(3, grid.neighbours8(Point::new(0, 0)).count());
+    assert_eq!(8, grid.neighbours8(Point::new(1, 1)).count());
+    assert_eq!("bdfh", grid.neighbours4(Point::new(1, 1)).map(|(_, c)| *c).sorted().collect::<String>());
+}
+
@@ -0,0 +1,187 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::{read_to_string, File},
+    io::{self, BufReader, Read},
+    str::FromStr,
+};
+
+/// Name used in errors for input that did not come from a named file; see `InputError::in_file`.
+pub const UNNAMED_INPUT: &str = "<input>";
+
+/// Everything that can go wrong while loading a puzzle input.
+#[derive(Debug)]
+pub enum InputError {
+    Io {
+        file: String,
+        source: io::Error,
+    },
+    Parse {
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Invalid {
+        file: String,
+        message: String,
+    },
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Io { file, source } => write!(f, "{file}: {source}"),
+            InputError::Parse { file, line, column, message } => write!(f, "{file}:{line}:{column}: {message}"),
+            InputError::Invalid { file, message } => write!(f, "{file}: {message}"),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl InputError {
+    pub fn invalid(message: impl Into<String>) -> InputError {
+        InputError::Invalid { file: UNNAMED_INPUT.to_string(), message: message.into() }
+    }
+
+    /// Say which part of the input a parse or validation error was found in.
+    pub fn in_section(self, section: &str) -> InputError {
+        match self {
+            InputError::Parse { file, line, column, message } => {
+                InputError::Parse { file, line, column, message: format!("{section} section: {message}") }
+            }
+            InputError::Invalid { file, message } => InputError::Invalid { file, message: format!("{section} section: {message}") },
+            io => io,
+        }
+    }
+
+    /// Attach the name of the file (or stream) the input was read from.
+    pub fn in_file(self, name: &str) -> InputError {
+        let file = name.to_string();
+
+        match self {
+            InputError::Io { source, .. } => InputError::Io { file, source },
+            InputError::Parse { line, column, message, .. } => InputError::Parse { file, line, column, message },
+            InputError::Invalid { message, .. } => InputError::Invalid { file, message },
+        }
+    }
+}
+
+pub fn read_input(filename: &str) -> Result<String, InputError> {
+    read_to_string(filename).map_err(|source| InputError::Io {
+        file: filename.to_string(),
+        source,
+    })
+}
+
+/// Open a file for reading line by line, for inputs too large to hold as one string.
+pub fn open_input(filename: &str) -> Result<BufReader<File>, InputError> {
+    File::open(filename)
+        .map(BufReader::new)
+        .map_err(|source| InputError::Io { file: filename.to_string(), source })
+}
+
+pub fn read_all<R: Read>(mut reader: R) -> Result<String, InputError> {
+    let mut contents = String::new();
+
+    reader.read_to_string(&mut contents).map_err(|source| InputError::Io {
+        file: UNNAMED_INPUT.to_string(),
+        source,
+    })?;
+
+    Ok(contents)
+}
+
+/// A single line of the input, remembering where it came from so errors can point at it.
+#[derive(Clone, Copy, Debug)]
+pub struct Line<'a> {
+    pub number: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Line<'a> {
+    /// Column (1-based, in chars) of `token`, which must be a slice of this line's text.
+    pub fn column_of(&self, token: &str) -> usize {
+        let offset = (token.as_ptr() as usize).saturating_sub(self.text.as_ptr() as usize).min(self.text.len());
+
+        self.text[..offset].chars().count() + 1
+    }
+
+    pub fn error_at(&self, column: usize, message: impl Into<String>) -> InputError {
+        InputError::Parse {
+            file: UNNAMED_INPUT.to_string(),
+            line: self.number,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(&self, token: &str, message: impl Into<String>) -> InputError {
+        self.error_at(self.column_of(token), message)
+    }
+
+    pub fn parse<T: FromStr>(&self, token: &str) -> Result<T, InputError> {
+        token
+            .parse::<T>()
+            .map_err(|_| self.error(token, format!("invalid number `{token}`")))
+    }
+
+    /// Like `Iterator::next` on a split of this line, but reports the end of the line when the field is missing.
+    pub fn expect<I: Iterator<Item = &'a str>>(&self, fields: &mut I, what: &str) -> Result<&'a str, InputError> {
+        fields
+            .next()
+            .ok_or_else(|| self.error_at(self.text.chars().count() + 1, format!("expected {what}")))
+    }
+}
+
+pub fn lines(contents: &str) -> impl Iterator<Item = Line<'_>> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, text)| Line { number: idx + 1, text })
+}
+
+/// Line and column (both 1-based) of a byte offset into a whole file.
+pub fn position_of(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[test]
+fn missing_file_reports_io_error() {
+    let err = read_input("does-not-exist.txt").unwrap_err();
+
+    assert!(matches!(err, InputError::Io { .. }));
+    assert!(err.to_string().starts_with("does-not-exist.txt: "));
+}
+
+#[test]
+fn bad_number_reports_line_and_column() {
+    let line = lines("1 2\n3 x4").nth(1).unwrap();
+
+    let mut fields = line.text.split(' ');
+    assert_eq!(3, line.parse::<i32>(line.expect(&mut fields, "a number").unwrap()).unwrap());
+
+    let err = line.parse::<i32>(line.expect(&mut fields, "a number").unwrap()).unwrap_err();
+    assert_eq!("sample.txt:2:3: invalid number `x4`", err.in_file("sample.txt").to_string());
+
+    let err = line.expect(&mut fields, "a number").unwrap_err();
+    assert_eq!("<input>:2:5: expected a number", err.to_string());
+}
+
+#[test]
+fn position_of_offset() {
+    assert_eq!((1, 1), position_of("abc\ndef", 0));
+    assert_eq!((2, 2), position_of("abc\ndef", 5));
+}
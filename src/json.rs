@@ -0,0 +1,118 @@
+use std::fmt::Write;
+
+use crate::solution::Answer;
+
+/// Just enough JSON to write flat result records.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// Already formatted as a JSON number.
+    Number(String),
+    String(String),
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Number(value.to_string())
+    }
+}
+
+impl From<u128> for Value {
+    fn from(value: u128) -> Self {
+        Value::Number(value.to_string())
+    }
+}
+
+impl From<Answer> for Value {
+    fn from(value: Answer) -> Self {
+        Value::Number(value.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(|v| v.into()).unwrap_or(Value::Null)
+    }
+}
+
+pub fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+
+    escaped.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+
+    escaped
+}
+
+/// A single-line JSON object with the fields in the order given.
+pub fn object(fields: Vec<(&str, Value)>) -> String {
+    let body: Vec<String> = fields.into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Null => "null".to_string(),
+                Value::Bool(b) => b.to_string(),
+                Value::Number(n) => n,
+                Value::String(s) => escape(&s),
+            };
+
+            format!("{}:{}", escape(key), value)
+        })
+        .collect();
+
+    format!("{{{}}}", body.join(","))
+}
+
+#[test]
+fn escape_control_characters() {
+    assert_eq!(r#""a\"b\\c\nd\u0001""#, escape("a\"b\\c\nd\u{1}"));
+}
+
+#[test]
+fn object_keeps_field_order() {
+    let record = object(vec![
+        ("day", 5u32.into()),
+        ("part", 'a'.into()),
+        ("answer", Answer::Signed(-3).into()),
+        ("error", Option::<String>::None.into()),
+    ]);
+
+    assert_eq!(r#"{"day":5,"part":"a","answer":-3,"error":null}"#, record);
+}
@@ -0,0 +1,21 @@
+//! Advent of Code 2024 solutions.
+//!
+//! Each `adventN` module exposes its parser, the helpers its solution is built from, and an `AdventN` type
+//! implementing [`solution::Solution`].
+
+pub mod advent1;
+pub mod advent2;
+pub mod advent3;
+pub mod advent4;
+pub mod advent5;
+pub mod advent6;
+pub mod answers;
+pub mod bitset;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod json;
+pub mod logging;
+pub mod runner;
+pub mod solution;
+pub mod timing;
@@ -0,0 +1,44 @@
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes log records to stderr so they never mix with answers on stdout.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{:5} {}] {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Map the number of `-v` flags to a level: warnings only by default, then info, debug and trace.
+pub fn level_for_verbosity(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the stderr logger; later calls only change the level.
+pub fn init(level: LevelFilter) {
+    let _ = log::set_logger(&LOGGER);
+
+    log::set_max_level(level);
+}
+
+#[test]
+fn verbosity_levels() {
+    assert_eq!(LevelFilter::Warn, level_for_verbosity(0));
+    assert_eq!(LevelFilter::Debug, level_for_verbosity(2));
+    assert_eq!(LevelFilter::Trace, level_for_verbosity(7));
+}
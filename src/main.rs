@@ -1,12 +1,616 @@
-mod advent1;
-mod advent2;
-mod advent3;
-mod advent4;
-mod advent5;
-mod advent6;
+use itertools::Itertools;
+use std::{collections::BTreeSet, env, fs, path::Path, process::ExitCode, thread, time::Duration};
 
+use advent_1::{
+    advent1::{self, Advent1},
+    advent5::{graph, Advent5, PageOrdering},
+    advent6::{self, render, GuardState, GuardStepper, Legend, Maze, TurnRule},
+    geometry::Point,
+    answers::{self, CheckResult, CheckStatus},
+    input::{self, InputError},
+    json, logging,
+    runner::{self, InputSource, Puzzle, Solved, DEFAULT_INPUTS_DIR, PUZZLES},
+    solution::Solution,
+    timing,
+};
 
+/// Flags accepted anywhere on the command line.
+struct Flags {
+    inputs_dir: String,
+    /// Set by `--bench <runs>` or `--time` (a single run).
+    bench_runs: Option<usize>,
+    /// Print one JSON record per line on stdout instead of text.
+    json: bool,
+    /// Number of `-v` flags given.
+    verbosity: u8,
+    /// Where `render` writes its SVG, if anywhere.
+    svg: Option<String>,
+    /// Directory `replay` dumps numbered frames into instead of animating.
+    frames_dir: Option<String>,
+    /// Pause between animated `replay` frames.
+    delay: Duration,
+    /// Extra barrier for `replay`.
+    obstruction: Option<Point>,
+    /// How guards turn in `guards`.
+    turn: TurnRule,
+    /// Where `graph` writes Graphviz DOT, if anywhere.
+    dot: Option<String>,
+    /// 1-based update whose rules `graph` restricts itself to.
+    update: Option<usize>,
+}
+
+struct Options {
+    inputs_dir: String,
+    /// `None` runs every day.
+    day: Option<u32>,
+    parts: Vec<char>,
+    /// Empty for the default input; several files are joined with blank lines.
+    inputs: Vec<String>,
+}
+
+fn input_source(options: &Options, puzzle: &Puzzle) -> Result<InputSource, InputError> {
+    let inputs: Vec<&str> = options.inputs.iter().map(String::as_str).collect();
+
+    match inputs.as_slice() {
+        [] => InputSource::from_inputs_dir(&options.inputs_dir, puzzle.default_inputs),
+        ["-"] => Ok(InputSource::Stdin),
+        files => InputSource::from_inputs_dir("", files),
+    }
+}
+
+fn usage() -> String {
+    [
+        "Usage: advent_1 [--inputs <dir>] [--json] <day|all> [a|b|both] [input...]",
+        "       advent_1 [--inputs <dir>] (--bench <runs> | --time) [day [input]]",
+        "       advent_1 [--inputs <dir>] [--json] check [answers]",
+        "       advent_1 [--inputs <dir>] [--svg <file>] render [input]",
+        "       advent_1 [--inputs <dir>] [--obstruction <x,y>] [--frames <dir> | --delay <ms>] replay [input]",
+        "       advent_1 [--inputs <dir>] [--turn right|left|reverse] guards [input]",
+        "       advent_1 [--inputs <dir>] similarity [input]",
+        "       advent_1 [--inputs <dir>] audit [input...]",
+        "       advent_1 [--inputs <dir>] [--update <n>] [--dot <file>] graph [input...]",
+        "       advent_1 list",
+        "",
+        "  input      path to the puzzle input, or - to read stdin; several files are joined with blank lines",
+        "  answers    registry of known answers (default: <inputs>/answers.txt)",
+        "  --inputs   directory holding the default inputs (default: inputs)",
+        "  --json     print one JSON record per day and part; diagnostics stay on stderr",
+        "  --bench    time parsing and each part over <runs> runs, for one day or all of them",
+        "  --time     same as --bench 1",
+        "  render     draw the day 6 guard's path with every looping obstruction marked O",
+        "  --svg      also write the render as SVG, marking heuristic and exact obstructions in separate colours",
+        "  replay     step through the day 6 guard's walk, animating it in the terminal",
+        "  --frames   write each replay frame to a numbered file in <dir> instead of animating",
+        "  --delay    milliseconds between animated frames (default: 50)",
+        "  --obstruction  place an extra barrier at x,y before replaying",
+        "  similarity break the day 1 similarity score down by value, largest contributions first",
+        "  audit      list every day 5 ordering rule each update breaks, with the positions of both pages;",
+        "             several input files are joined with blank lines, as for the default rules and updates",
+        "  graph      analyse the day 5 rules as a graph: cycles, whether they form a DAG, and the transitive reduction",
+        "  --update   restrict graph to the rules between the pages of update <n> (numbered as in audit)",
+        "  --dot      also write the graph, or its transitive reduction when there is one, as Graphviz DOT",
+        "  guards     walk every guard in a day 6 maze at once, reporting cells visited, collisions and loops",
+        "  --turn     which way guards turn at a barrier (default: right)",
+        "  -v         log progress to stderr; repeat (-vv, -vvv) for debug and trace output",
+    ]
+    .join("\n")
+}
+
+fn list() {
+    println!("Available puzzles:");
+
+    for puzzle in PUZZLES.iter() {
+        println!("  day {} parts a, b (default input: {})", puzzle.day, puzzle.default_inputs.join(" + "));
+    }
+}
+
+fn solved_records(day: u32, input: &str, solved: &Solved) -> Vec<String> {
+    solved.parts.iter()
+        .map(|result| {
+            json::object(vec![
+                ("day", day.into()),
+                ("part", result.part.into()),
+                ("input", input.into()),
+                ("status", "ok".into()),
+                ("answer", result.answer.into()),
+                ("parse_ns", solved.parse_time.as_nanos().into()),
+                ("time_ns", result.elapsed.as_nanos().into()),
+            ])
+        })
+        .collect()
+}
+
+fn error_records(day: u32, parts: &[char], input: &str, err: &InputError) -> Vec<String> {
+    parts.iter()
+        .map(|part| {
+            json::object(vec![
+                ("day", day.into()),
+                ("part", (*part).into()),
+                ("input", input.into()),
+                ("status", "error".into()),
+                ("error", err.to_string().into()),
+            ])
+        })
+        .collect()
+}
+
+fn solve(options: &Options, json: bool) -> ExitCode {
+    let puzzles: Vec<&Puzzle> = match options.day {
+        Some(day) => match runner::find_puzzle(day) {
+            Some(puzzle) => vec![puzzle],
+            None => {
+                eprintln!("No solution available for day {day}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => PUZZLES.iter().collect(),
+    };
+
+    let mut failed = false;
+
+    for puzzle in puzzles {
+        let source = input_source(options, puzzle);
+        let input = source.as_ref().map(|s| s.name().to_string()).unwrap_or_default();
+
+        log::info!("Solving day {} from {}", puzzle.day, input);
+
+        match source.and_then(|source| (puzzle.solve)(&source, &options.parts)) {
+            Ok(solved) if json => {
+                solved_records(puzzle.day, &input, &solved).iter().for_each(|r| println!("{r}"));
+            }
+            Ok(solved) => {
+                for result in solved.parts {
+                    println!("Day {} part {}: {}", puzzle.day, result.part, result.answer);
+                }
+            }
+            Err(err) => {
+                failed = true;
+
+                if json {
+                    error_records(puzzle.day, &options.parts, &input, &err).iter().for_each(|r| println!("{r}"));
+                } else {
+                    eprintln!("error: {err}");
+                }
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn check_record(result: &CheckResult) -> String {
+    let (status, actual, error) = match &result.status {
+        CheckStatus::Pass => ("pass", result.expected.clone(), None),
+        CheckStatus::Fail { actual } => ("fail", Some(actual.clone()), None),
+        CheckStatus::Missing { actual } => ("missing", Some(actual.clone()), None),
+        CheckStatus::Error(err) => ("error", None, Some(err.clone())),
+    };
+
+    json::object(vec![
+        ("day", result.day.into()),
+        ("part", result.part.into()),
+        ("input", result.input.as_str().into()),
+        ("status", status.into()),
+        ("expected", result.expected.clone().into()),
+        ("actual", actual.into()),
+        ("error", error.into()),
+    ])
+}
+
+fn check(inputs_dir: &str, answers_file: Option<&str>, json: bool) -> ExitCode {
+    let default_answers = Path::new(inputs_dir).join(answers::ANSWERS_FILE).to_string_lossy().into_owned();
+    let answers_file = answers_file.unwrap_or(&default_answers);
+
+    let expected = input::read_input(answers_file)
+        .and_then(|contents| answers::read_answers(&contents).map_err(|e| e.in_file(answers_file)));
+
+    let expected = match expected {
+        Ok(expected) => expected,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let results = answers::check_all(inputs_dir, &expected);
+
+    if json {
+        results.iter().for_each(|r| println!("{}", check_record(r)));
+    } else {
+        println!("{}", answers::format_table(&results));
+    }
+
+    if results.iter().all(|r| matches!(r.status, CheckStatus::Pass | CheckStatus::Missing { .. })) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn bench(inputs_dir: &str, runs: usize, positional: &[&str]) -> ExitCode {
+    let puzzles: Vec<&Puzzle> = match positional.first() {
+        Some(day) => match day.parse::<u32>().ok().and_then(runner::find_puzzle) {
+            Some(puzzle) => vec![puzzle],
+            None => {
+                eprintln!("No solution available for day {day}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => PUZZLES.iter().collect(),
+    };
+
+    let mut timings = vec![];
+
+    for puzzle in puzzles {
+        let source = match positional.get(1) {
+            Some(&"-") => Ok(InputSource::Stdin),
+            Some(path) => Ok(InputSource::File(path.to_string())),
+            None => InputSource::from_inputs_dir(inputs_dir, puzzle.default_inputs),
+        };
+
+        match source.and_then(|source| (puzzle.time)(&source, runs)) {
+            Ok(timing) => timings.push((puzzle.day, timing)),
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    print!("{}", timing::format_timings(&timings, runs));
+
+    ExitCode::SUCCESS
+}
+
+/// Read the `inputs` files joined together, or the default input for `day` if there are none, and parse it with `parse`.
+fn load_input<T>(inputs_dir: &str, day: u32, inputs: &[&str], parse: impl FnOnce(&str) -> Result<T, InputError>) -> Result<T, InputError> {
+    let source = match inputs {
+        [] => InputSource::from_inputs_dir(inputs_dir, runner::find_puzzle(day).map(|p| p.default_inputs).unwrap_or_default())?,
+        ["-"] => InputSource::Stdin,
+        files => InputSource::from_inputs_dir("", files)?,
+    };
+
+    let contents = source.read_to_string()?;
+
+    parse(&contents).map_err(|e| e.in_file(source.name()))
+}
+
+fn single_guard_maze(contents: &str) -> Result<(Maze, GuardState), InputError> {
+    advent6::parse_maze(contents, &Legend::default())
+}
+
+fn render(inputs_dir: &str, input: &[&str], svg: Option<&str>) -> ExitCode {
+    let (maze, guard) = match load_input(inputs_dir, 6, input, single_guard_maze) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (trail, _) = advent6::guard_trail(&maze, guard, None);
+    let exact = advent6::loop_obstructions(&maze, guard);
+
+    print!("{}", render::render_text(&maze, &trail, &exact));
+
+    if let Some(svg) = svg {
+        let (_, _, heuristic) = advent6::move_guard_until_exit(&maze, guard);
+        let heuristic: BTreeSet<_> = heuristic.into_iter().collect();
+
+        let both = exact.intersection(&heuristic).copied().collect();
+        let exact_only = exact.difference(&heuristic).copied().collect();
+        let heuristic_only = heuristic.difference(&exact).copied().collect();
+
+        let overlays = [
+            render::Overlay { label: "found by both", colour: "green", points: &both },
+            render::Overlay { label: "missed by heuristic", colour: "red", points: &exact_only },
+            render::Overlay { label: "heuristic only, no loop", colour: "orange", points: &heuristic_only },
+        ];
+
+        if let Err(err) = fs::write(svg, render::render_svg(&maze, &trail, &overlays)) {
+            eprintln!("error: {svg}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn replay(inputs_dir: &str, input: &[&str], flags: &Flags) -> ExitCode {
+    let (maze, guard) = match load_input(inputs_dir, 6, input, single_guard_maze) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(dir) = &flags.frames_dir {
+        if let Err(err) = fs::create_dir_all(dir) {
+            eprintln!("error: {dir}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let obstructions: BTreeSet<Point> = flags.obstruction.into_iter().collect();
+    let mut stepper = GuardStepper::new(&maze, guard, flags.obstruction);
+    let mut path = vec![];
+
+    for state in stepper.by_ref() {
+        path.push(state);
+
+        let frame = format!(
+            "step {} at ({}, {}) facing {:?}\n{}",
+            path.len() - 1,
+            state.position.x,
+            state.position.y,
+            state.facing,
+            render::render_frame(&maze, &path, &obstructions)
+        );
+
+        match &flags.frames_dir {
+            Some(dir) => {
+                let file = Path::new(dir).join(format!("frame-{:05}.txt", path.len() - 1));
+
+                if let Err(err) = fs::write(&file, frame) {
+                    eprintln!("error: {}: {err}", file.display());
+                    return ExitCode::FAILURE;
+                }
+            }
+            None => {
+                // Clear the screen and home the cursor before each frame
+                print!("\x1b[2J\x1b[H{frame}");
+                thread::sleep(flags.delay);
+            }
+        }
+    }
+
+    println!("Guard {:?} after {} states", stepper.outcome().unwrap_or(advent6::WalkOutcome::Exits), path.len());
+
+    ExitCode::SUCCESS
+}
+
+fn audit(inputs_dir: &str, inputs: &[&str]) -> ExitCode {
+    let advent5 = match load_input(inputs_dir, 5, inputs, Advent5::parse) {
+        Ok(advent5) => advent5,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    print!("{}", advent5.audit());
+
+    ExitCode::SUCCESS
+}
+
+/// How many of the largest contributions `similarity` lists.
+const TOP_CONTRIBUTIONS: usize = 10;
+
+fn similarity(inputs_dir: &str, inputs: &[&str]) -> ExitCode {
+    let advent1 = match load_input(inputs_dir, 1, inputs, Advent1::parse) {
+        Ok(advent1) => advent1,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (left, right) = (advent1.left_frequencies(), advent1.right_frequencies());
+    let breakdown = advent1::similarity_breakdown(&left, &right);
+
+    println!("similarity: {}", advent1::similarity(&left, &right));
+    println!("matched similarity: {}", advent1::matched_similarity(&left, &right));
+    println!("{} of {} distinct left values also appear on the right", breakdown.len(), left.distinct());
+    println!("{:>10} {:>6} {:>6} {:>12}", "value", "left", "right", "score");
+
+    for c in breakdown.iter().take(TOP_CONTRIBUTIONS) {
+        println!("{:>10} {:>6} {:>6} {:>12}", c.value, c.left_count, c.right_count, c.score);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn rule_graph(inputs_dir: &str, inputs: &[&str], update: Option<usize>, dot: Option<&str>) -> ExitCode {
+    let advent5 = match load_input(inputs_dir, 5, inputs, Advent5::parse) {
+        Ok(advent5) => advent5,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rules = match update {
+        Some(n) => match advent5.updates().get(n - 1) {
+            Some(pages) => {
+                println!("update {n}: {}", pages.iter().join(","));
+                advent5.rules().restricted_to(pages)
+            }
+            None => {
+                eprintln!("error: there are only {} updates", advent5.updates().len());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => advent5.rules().clone(),
+    };
+
+    let report = graph::analyse(&rules);
+
+    print!("{report}");
+
+    if let Some(dot) = dot {
+        let exported = match report.reduction {
+            Some(reduction) => PageOrdering::from_rules(reduction).unwrap_or_default(),
+            None => rules,
+        };
+
+        if let Err(err) = fs::write(dot, graph::to_dot(&exported)) {
+            eprintln!("error: {dot}: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn guards(inputs_dir: &str, input: &[&str], rule: TurnRule) -> ExitCode {
+    let maze = match load_input(inputs_dir, 6, input, advent6::read_maze_lines) {
+        Ok(maze) => maze,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    print!("{}", advent6::simulate_guards(&maze, &advent6::find_guards(&maze), rule));
+
+    ExitCode::SUCCESS
+}
+
+fn parse_turn_rule(text: &str) -> Option<TurnRule> {
+    match text {
+        "right" => Some(TurnRule::Right),
+        "left" => Some(TurnRule::Left),
+        "reverse" => Some(TurnRule::Reverse),
+        _ => None,
+    }
+}
+
+fn parse_point(text: &str) -> Option<Point> {
+    let (x, y) = text.split_once(',')?;
+
+    Some(Point::new(x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+fn parse_parts(part: &str) -> Option<Vec<char>> {
+    match part {
+        "a" => Some(vec!['a']),
+        "b" => Some(vec!['b']),
+        "both" => Some(vec!['a', 'b']),
+        _ => None,
+    }
+}
+
+/// Split the command line into flags and the remaining positional arguments.
+fn parse_flags(args: &[String]) -> Option<(Flags, Vec<&str>)> {
+    let mut flags = Flags {
+        inputs_dir: DEFAULT_INPUTS_DIR.to_string(),
+        bench_runs: None,
+        json: false,
+        verbosity: 0,
+        svg: None,
+        frames_dir: None,
+        delay: Duration::from_millis(50),
+        obstruction: None,
+        turn: TurnRule::Right,
+        dot: None,
+        update: None,
+    };
+    let mut positional: Vec<&str> = vec![];
+
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--inputs" => flags.inputs_dir = iter.next()?.clone(),
+            "--bench" => flags.bench_runs = Some(iter.next()?.parse::<usize>().ok().filter(|runs| *runs > 0)?),
+            "--time" => flags.bench_runs = Some(1),
+            "--json" => flags.json = true,
+            "--svg" => flags.svg = Some(iter.next()?.clone()),
+            "--frames" => flags.frames_dir = Some(iter.next()?.clone()),
+            "--delay" => flags.delay = Duration::from_millis(iter.next()?.parse().ok()?),
+            "--obstruction" => flags.obstruction = Some(parse_point(iter.next()?)?),
+            "--turn" => flags.turn = parse_turn_rule(iter.next()?)?,
+            "--dot" => flags.dot = Some(iter.next()?.clone()),
+            "--update" => flags.update = Some(iter.next()?.parse::<usize>().ok().filter(|n| *n > 0)?),
+            "--verbose" => flags.verbosity += 1,
+            v if v.len() > 1 && v.starts_with('-') && v[1..].chars().all(|c| c == 'v') => {
+                flags.verbosity += (v.len() - 1) as u8;
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    Some((flags, positional))
+}
+
+fn parse_options(inputs_dir: String, positional: &[&str]) -> Option<Options> {
+    let day = match *positional.first()? {
+        "all" => None,
+        day => Some(day.parse::<u32>().ok()?),
+    };
+
+    let inputs: Vec<String> = positional.iter().skip(2).map(|s| s.to_string()).collect();
+
+    // Input files only make sense for a single day
+    if day.is_none() && !inputs.is_empty() {
+        return None;
+    }
+
+    Some(Options {
+        inputs_dir,
+        day,
+        parts: parse_parts(positional.get(1).copied().unwrap_or("both"))?,
+        inputs,
+    })
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some((flags, positional)) = parse_flags(&args) else {
+        eprintln!("{}", usage());
+        return ExitCode::FAILURE;
+    };
+
+    logging::init(logging::level_for_verbosity(flags.verbosity));
+
+    if let Some(runs) = flags.bench_runs {
+        if positional.len() > 2 {
+            eprintln!("{}", usage());
+            return ExitCode::FAILURE;
+        }
+
+        return bench(&flags.inputs_dir, runs, &positional);
+    }
+
+    match positional.first().copied() {
+        Some("list") => {
+            list();
+            return ExitCode::SUCCESS;
+        }
+        Some("check") if positional.len() <= 2 => {
+            return check(&flags.inputs_dir, positional.get(1).copied(), flags.json);
+        }
+        Some("render") if positional.len() <= 2 => {
+            return render(&flags.inputs_dir, &positional[1..], flags.svg.as_deref());
+        }
+        Some("audit") => {
+            return audit(&flags.inputs_dir, &positional[1..]);
+        }
+        Some("similarity") if positional.len() <= 2 => {
+            return similarity(&flags.inputs_dir, &positional[1..]);
+        }
+        Some("graph") => {
+            return rule_graph(&flags.inputs_dir, &positional[1..], flags.update, flags.dot.as_deref());
+        }
+        Some("guards") if positional.len() <= 2 => {
+            return guards(&flags.inputs_dir, &positional[1..], flags.turn);
+        }
+        Some("replay") if positional.len() <= 2 => {
+            return replay(&flags.inputs_dir, &positional[1..], &flags);
+        }
+        _ => {}
+    }
+
+    let Some(options) = parse_options(flags.inputs_dir, &positional) else {
+        eprintln!("{}", usage());
+        return ExitCode::FAILURE;
+    };
 
-fn main() {
-    // advent4::advent4a();
+    solve(&options, flags.json)
 }
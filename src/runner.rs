@@ -0,0 +1,137 @@
+use std::{
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    advent1, advent2, advent3, advent4, advent5, advent6,
+    input::{self, InputError},
+    solution::{Answer, Solution},
+    timing::{self, Timings},
+};
+
+pub const DEFAULT_INPUTS_DIR: &str = "inputs";
+
+pub struct PartResult {
+    pub part: char,
+    pub answer: Answer,
+    pub elapsed: Duration,
+}
+
+/// The answers for the requested parts of one day, with how long each step took (`parse_time` includes reading the input).
+pub struct Solved {
+    pub parse_time: Duration,
+    pub parts: Vec<PartResult>,
+}
+
+/// Where the puzzle input is read from.
+pub enum InputSource {
+    Stdin,
+    File(String),
+    Text { name: String, contents: String },
+}
+
+impl InputSource {
+    /// Input made of `files` inside `inputs_dir`; several files are joined with a blank line between them.
+    pub fn from_inputs_dir(inputs_dir: &str, files: &[&str]) -> Result<InputSource, InputError> {
+        let paths: Vec<String> = files.iter()
+            .map(|f| Path::new(inputs_dir).join(f).to_string_lossy().into_owned())
+            .collect();
+
+        if paths.len() == 1 {
+            return Ok(InputSource::File(paths[0].clone()));
+        }
+
+        let contents = paths.iter()
+            .map(|p| input::read_input(p).map(|c| c.trim_end().to_string()))
+            .collect::<Result<Vec<String>, InputError>>()?;
+
+        Ok(InputSource::Text { name: paths.join(" + "), contents: contents.join("\n\n") })
+    }
+
+    /// Name used in error messages for this source.
+    pub fn name(&self) -> &str {
+        match self {
+            InputSource::Stdin => "<stdin>",
+            InputSource::File(path) => path,
+            InputSource::Text { name, .. } => name,
+        }
+    }
+
+    pub fn read_to_string(&self) -> Result<String, InputError> {
+        match self {
+            InputSource::Stdin => input::read_all(io::stdin().lock()).map_err(|e| e.in_file(self.name())),
+            InputSource::File(path) => input::read_input(path),
+            InputSource::Text { contents, .. } => Ok(contents.clone()),
+        }
+    }
+}
+
+pub struct Puzzle {
+    pub day: u32,
+    pub default_inputs: &'static [&'static str],
+    pub solve: fn(&InputSource, &[char]) -> Result<Solved, InputError>,
+    pub time: fn(&InputSource, usize) -> Result<Timings, InputError>,
+}
+
+// Day 5 also keeps its rules and updates in separate files; `check` runs both layouts
+pub const PUZZLES: [Puzzle; 6] = [
+    Puzzle::new::<advent1::Advent1>(1, &["advent1-input.txt"]),
+    Puzzle::new::<advent2::Advent2>(2, &["advent2a-input.txt"]),
+    Puzzle::new::<advent3::Advent3>(3, &["advent3a-input.txt"]),
+    Puzzle::new::<advent4::Advent4>(4, &["advent4a-input.txt"]),
+    Puzzle::new::<advent5::Advent5>(5, &["advent5a-input.txt"]),
+    Puzzle::new::<advent6::Advent6>(6, &["advent6a-input.txt"]),
+];
+
+impl Puzzle {
+    const fn new<S: Solution>(day: u32, default_inputs: &'static [&'static str]) -> Puzzle {
+        Puzzle { day, default_inputs, solve: solve::<S>, time: time::<S> }
+    }
+}
+
+pub const PARTS: [char; 2] = ['a', 'b'];
+
+pub fn find_puzzle(day: u32) -> Option<&'static Puzzle> {
+    PUZZLES.iter().find(|p| p.day == day)
+}
+
+fn solve<S: Solution>(source: &InputSource, parts: &[char]) -> Result<Solved, InputError> {
+    let start = Instant::now();
+
+    let solution = match source {
+        InputSource::Stdin => S::from_reader(io::stdin().lock()).map_err(|e| e.in_file("<stdin>")),
+        InputSource::File(path) => S::from_file(path),
+        InputSource::Text { name, contents } => S::parse(contents).map_err(|e| e.in_file(name)),
+    }?;
+
+    let parse_time = start.elapsed();
+
+    log::info!("Parsed {} in {:?}", source.name(), parse_time);
+
+    let parts = parts.iter()
+        .map(|part| {
+            let start = Instant::now();
+
+            let answer = match part {
+                'a' => solution.part1(),
+                _ => solution.part2(),
+            };
+
+            let elapsed = start.elapsed();
+
+            log::info!("Part {part} took {:?}", elapsed);
+
+            PartResult { part: *part, answer, elapsed }
+        })
+        .collect();
+
+    Ok(Solved { parse_time, parts })
+}
+
+fn time<S: Solution>(source: &InputSource, runs: usize) -> Result<Timings, InputError> {
+    let contents = source.read_to_string()?;
+
+    timing::time_solution::<S>(&contents, runs).map_err(|e| e.in_file(source.name()))
+}
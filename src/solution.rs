@@ -0,0 +1,70 @@
+use std::{fmt::Display, io::BufRead};
+
+use crate::input::{self, InputError};
+
+/// The answer to one part of a day's puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Signed(i64),
+    Unsigned(u64),
+    /// For totals that can outgrow 64 bits.
+    Wide(i128),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Signed(v) => write!(f, "{v}"),
+            Answer::Unsigned(v) => write!(f, "{v}"),
+            Answer::Wide(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(value: i32) -> Self {
+        Answer::Signed(value as i64)
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Answer::Signed(value)
+    }
+}
+
+impl From<i128> for Answer {
+    fn from(value: i128) -> Self {
+        i64::try_from(value).map(Answer::Signed).unwrap_or(Answer::Wide(value))
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(value: u32) -> Self {
+        Answer::Unsigned(value as u64)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Unsigned(value as u64)
+    }
+}
+
+/// Shared shape of every day: parse the input once, then answer each part from the parsed data.
+pub trait Solution: Sized {
+    /// Parse the full text of a puzzle input.
+    fn parse(input: &str) -> Result<Self, InputError>;
+
+    fn from_reader<R: BufRead>(reader: R) -> Result<Self, InputError> {
+        Self::parse(&input::read_all(reader)?)
+    }
+
+    fn from_file(filename: &str) -> Result<Self, InputError> {
+        Self::parse(&input::read_input(filename)?).map_err(|e| e.in_file(filename))
+    }
+
+    fn part1(&self) -> Answer;
+
+    fn part2(&self) -> Answer;
+}
@@ -0,0 +1,97 @@
+use std::{
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::{input::InputError, solution::Solution};
+
+/// Spread of the samples taken for one stage of a solution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Stats {
+        samples.sort();
+
+        Stats {
+            min: samples.first().copied().unwrap_or_default(),
+            median: samples.get(samples.len() / 2).copied().unwrap_or_default(),
+            max: samples.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Timings {
+    pub parse: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
+
+fn sample<T>(runs: usize, mut f: impl FnMut() -> T) -> Vec<Duration> {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect()
+}
+
+/// Time parsing and each part separately, `runs` times each.
+pub fn time_solution<S: Solution>(input: &str, runs: usize) -> Result<Timings, InputError> {
+    let runs = runs.max(1);
+
+    // Parse once up front so a bad input is reported before any timing happens
+    let solution = S::parse(input)?;
+
+    let parse = sample(runs, || S::parse(input));
+    let part1 = sample(runs, || solution.part1());
+    let part2 = sample(runs, || solution.part2());
+
+    Ok(Timings {
+        parse: Stats::from_samples(parse),
+        part1: Stats::from_samples(part1),
+        part2: Stats::from_samples(part2),
+    })
+}
+
+pub fn format_timings(timings: &[(u32, Timings)], runs: usize) -> String {
+    let mut table = String::new();
+
+    writeln!(table, "{} run(s) per stage", runs.max(1)).unwrap();
+    writeln!(table, "day stage  {:>12} {:>12} {:>12}", "min", "median", "max").unwrap();
+
+    for (day, timing) in timings {
+        for (stage, stats) in [("parse", timing.parse), ("part1", timing.part1), ("part2", timing.part2)] {
+            writeln!(
+                table,
+                "{:>3} {:6} {:>12} {:>12} {:>12}",
+                day,
+                stage,
+                format!("{:.3?}", stats.min),
+                format!("{:.3?}", stats.median),
+                format!("{:.3?}", stats.max),
+            )
+            .unwrap();
+        }
+    }
+
+    table
+}
+
+#[test]
+fn stats_from_samples() {
+    let samples = [5, 1, 3, 2, 4].map(Duration::from_millis).to_vec();
+
+    let stats = Stats::from_samples(samples);
+
+    assert_eq!(Duration::from_millis(1), stats.min);
+    assert_eq!(Duration::from_millis(3), stats.median);
+    assert_eq!(Duration::from_millis(5), stats.max);
+}